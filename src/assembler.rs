@@ -0,0 +1,1251 @@
+use crate::cpu::CpuModel;
+use crate::error::{AssembleError, AssembleErrorReason};
+use crate::instructions::encoding::Encoding;
+use crate::instructions::generic::{Instruction, Op};
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::prefix::{Prefixes, Repeat};
+use crate::instructions::table::ENCODINGS;
+use crate::memory::{Displacement, EffectiveAddress, ExtendedAddress};
+use crate::register::Register;
+use crate::segment_register::SegmentRegister;
+use std::collections::{HashMap, HashSet};
+
+type AssembleResult<T> = Result<T, AssembleErrorReason>;
+
+/// How many times the program is laid out before labels are given up on settling.
+const MAX_PASSES: usize = 32;
+
+/// Mnemonics nasm accepts besides the ones the decoder writes.
+const ALIASES: &[(&str, &str)] = &[
+    ("jz", "je"),
+    ("jnz", "jne"),
+    ("jc", "jb"),
+    ("jnae", "jb"),
+    ("jae", "jnb"),
+    ("jnc", "jnb"),
+    ("jna", "jbe"),
+    ("ja", "jnbe"),
+    ("jpe", "jp"),
+    ("jpo", "jnp"),
+    ("jnge", "jl"),
+    ("jge", "jnl"),
+    ("jng", "jle"),
+    ("jg", "jnle"),
+    ("loopz", "loope"),
+    ("loopnz", "loopne"),
+    ("sal", "shl"),
+    ("xlatb", "xlat"),
+];
+
+/// Assembles the `bits 16` nasm syntax [`decode`](crate::decode) writes into machine code:
+/// labels, `$` and arithmetic on them, the `byte`, `word` and `dword` size keywords, `short`,
+/// `near` and `far`, segment overrides, the prefixes and `db`. `cpu 8086`, `cpu 186` and
+/// `cpu 386` limit the instructions to a model, which is the 80386 until one is selected.
+///
+/// Relative branches to labels take the short form wherever it reaches, like nasm picks them.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    Program::parse(source)?.assemble()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Number(i64),
+    Symbol(char),
+}
+
+fn is_word_start(value: char) -> bool {
+    value.is_ascii_alphabetic() || matches!(value, '_' | '.' | '?' | '@' | '$')
+}
+
+fn is_word_part(value: char) -> bool {
+    value.is_ascii_alphanumeric() || matches!(value, '_' | '.' | '?' | '@' | '$' | '#' | '~')
+}
+
+fn tokenize(line: &str) -> AssembleResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(&value) = chars.peek() {
+        match value {
+            ';' => break,
+            value if value.is_whitespace() => {
+                chars.next();
+            }
+            value if value.is_ascii_digit() || is_word_start(value) => {
+                let mut text = String::new();
+
+                while let Some(&value) = chars.peek().filter(|&&value| is_word_part(value)) {
+                    text.push(value);
+                    chars.next();
+                }
+
+                tokens.push(if text.starts_with(|value: char| value.is_ascii_digit()) {
+                    Token::Number(number(&text)?)
+                } else {
+                    Token::Word(text)
+                });
+            }
+            '[' | ']' | ',' | ':' | '+' | '-' | '*' | '/' | '(' | ')' => {
+                tokens.push(Token::Symbol(value));
+                chars.next();
+            }
+            value => {
+                return Err(AssembleErrorReason::Syntax(format!(
+                    "unexpected character {value}"
+                )))
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a decimal, `0x` hexadecimal or `0b` binary number, or a hexadecimal one with an `h`
+/// suffix.
+fn number(text: &str) -> AssembleResult<i64> {
+    let text = text.to_ascii_lowercase().replace('_', "");
+
+    let parsed = if let Some(digits) = text.strip_prefix("0x") {
+        i64::from_str_radix(digits, 16)
+    } else if let Some(digits) = text.strip_suffix('h') {
+        i64::from_str_radix(digits, 16)
+    } else if let Some(digits) = text.strip_prefix("0b") {
+        i64::from_str_radix(digits, 2)
+    } else {
+        text.parse()
+    };
+
+    parsed.map_err(|_| AssembleErrorReason::Syntax(format!("invalid number {text}")))
+}
+
+fn register(name: &str) -> Option<Register> {
+    let name = name.to_ascii_lowercase();
+    let registers = (0..16).map(Register::from);
+    let extended = registers
+        .clone()
+        .filter(|register| register.is_wide())
+        .map(Register::extended);
+
+    registers
+        .chain(extended)
+        .find(|register| register.to_string() == name)
+}
+
+fn segment_register(name: &str) -> Option<SegmentRegister> {
+    let name = name.to_ascii_lowercase();
+
+    (0..4)
+        .map(SegmentRegister::from)
+        .find(|register| register.to_string() == name)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Size {
+    Byte,
+    Word,
+    DoubleWord,
+}
+
+impl Size {
+    fn of(register: Register) -> Self {
+        if register.is_extended() {
+            Size::DoubleWord
+        } else if register.is_wide() {
+            Size::Word
+        } else {
+            Size::Byte
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Distance {
+    Short,
+    Near,
+    Far,
+}
+
+#[derive(Clone)]
+enum Expression {
+    Number(i64),
+    /// `$`, the start of the instruction.
+    Here,
+    Label(String),
+    Register(Register),
+    Negate(Box<Expression>),
+    Add(Box<Expression>, Box<Expression>),
+    Subtract(Box<Expression>, Box<Expression>),
+    Multiply(Box<Expression>, Box<Expression>),
+    Divide(Box<Expression>, Box<Expression>),
+}
+
+/// A register in an address, with the factor it is scaled by.
+#[derive(Copy, Clone)]
+struct Term {
+    register: Register,
+    scale: i64,
+    /// Whether the register is written with a factor, which makes it an index under `nosplit`.
+    is_scaled: bool,
+}
+
+/// What an expression comes to: a number, plus the registers of an address.
+#[derive(Clone, Default)]
+struct Sum {
+    constant: i64,
+    terms: Vec<Term>,
+}
+
+impl Sum {
+    fn constant(self) -> AssembleResult<i64> {
+        match self.terms.first() {
+            Some(term) => Err(AssembleErrorReason::Syntax(format!(
+                "register {} outside of an address",
+                term.register
+            ))),
+            None => Ok(self.constant),
+        }
+    }
+
+    fn add(mut self, other: Sum) -> Self {
+        self.constant = self.constant.wrapping_add(other.constant);
+
+        for term in other.terms {
+            match self
+                .terms
+                .iter_mut()
+                .find(|existing| existing.register == term.register)
+            {
+                Some(existing) => {
+                    existing.scale += term.scale;
+                    existing.is_scaled = true;
+                }
+                None => self.terms.push(term),
+            }
+        }
+
+        self
+    }
+
+    fn scale(mut self, factor: i64) -> Self {
+        self.constant = self.constant.wrapping_mul(factor);
+
+        for term in &mut self.terms {
+            term.scale *= factor;
+            term.is_scaled = true;
+        }
+
+        self
+    }
+}
+
+/// Where an expression is evaluated: the start of its instruction and the labels placed so far.
+struct Context<'a> {
+    here: i64,
+    placed: &'a HashMap<String, i64>,
+    previous: &'a HashMap<String, i64>,
+    defined: &'a HashSet<String>,
+}
+
+impl Context<'_> {
+    /// Labels further down than the line being assembled are where the last pass put them, or
+    /// at the line itself on the first pass, which keeps branches to them short until they are
+    /// known to be out of reach.
+    fn label(&self, name: &str) -> AssembleResult<i64> {
+        match self.placed.get(name).or_else(|| self.previous.get(name)) {
+            Some(&value) => Ok(value),
+            None if self.defined.contains(name) => Ok(self.here),
+            None => Err(AssembleErrorReason::UndefinedLabel(name.to_string())),
+        }
+    }
+}
+
+impl Expression {
+    fn evaluate(&self, context: &Context) -> AssembleResult<Sum> {
+        let constant = |constant| Sum {
+            constant,
+            ..Default::default()
+        };
+
+        Ok(match self {
+            Expression::Number(value) => constant(*value),
+            Expression::Here => constant(context.here),
+            Expression::Label(name) => constant(context.label(name)?),
+            Expression::Register(register) => Sum {
+                constant: 0,
+                terms: vec![Term {
+                    register: *register,
+                    scale: 1,
+                    is_scaled: false,
+                }],
+            },
+            Expression::Negate(value) => value.evaluate(context)?.scale(-1),
+            Expression::Add(lhs, rhs) => lhs.evaluate(context)?.add(rhs.evaluate(context)?),
+            Expression::Subtract(lhs, rhs) => {
+                lhs.evaluate(context)?.add(rhs.evaluate(context)?.scale(-1))
+            }
+            Expression::Multiply(lhs, rhs) => {
+                let (lhs, rhs) = (lhs.evaluate(context)?, rhs.evaluate(context)?);
+
+                match (lhs.terms.is_empty(), rhs.terms.is_empty()) {
+                    (_, true) => lhs.scale(rhs.constant),
+                    (true, false) => rhs.scale(lhs.constant),
+                    (false, false) => {
+                        return Err(AssembleErrorReason::Syntax(
+                            "registers multiplied together".to_string(),
+                        ))
+                    }
+                }
+            }
+            Expression::Divide(lhs, rhs) => {
+                let (lhs, rhs) = (
+                    lhs.evaluate(context)?.constant()?,
+                    rhs.evaluate(context)?.constant()?,
+                );
+
+                if rhs == 0 {
+                    return Err(AssembleErrorReason::Syntax("division by zero".to_string()));
+                }
+
+                constant(lhs / rhs)
+            }
+        })
+    }
+
+    fn constant(&self, context: &Context) -> AssembleResult<i64> {
+        self.evaluate(context)?.constant()
+    }
+}
+
+#[derive(Clone)]
+enum Written {
+    Register(Register),
+    SegmentRegister(SegmentRegister),
+    Memory {
+        segment: Option<SegmentRegister>,
+        /// The width `[byte ...]` or `[dword ...]` forces the displacement to.
+        displacement_size: Option<Size>,
+        is_nosplit: bool,
+        address: Expression,
+    },
+    Immediate(Expression),
+    FarPointer(Expression, Expression),
+}
+
+/// An operand as written, along with the keywords ahead of it.
+#[derive(Clone)]
+struct WrittenOperand {
+    size: Option<Size>,
+    distance: Option<Distance>,
+    value: Written,
+}
+
+#[derive(Clone)]
+struct Source {
+    mnemonic: String,
+    prefixes: Prefixes,
+    operands: Vec<WrittenOperand>,
+}
+
+#[derive(Clone)]
+enum Body {
+    Data(Vec<Expression>),
+    Instruction(Source),
+}
+
+#[derive(Clone)]
+struct Statement {
+    line: usize,
+    label: Option<String>,
+    body: Option<Body>,
+    model: CpuModel,
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    /// The last label not starting with a dot, which labels starting with one belong to.
+    scope: &'a str,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    fn is_done(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        let is_symbol = self.peek() == Some(&Token::Symbol(symbol));
+
+        if is_symbol {
+            self.position += 1;
+        }
+
+        is_symbol
+    }
+
+    fn expect(&mut self, symbol: char) -> AssembleResult<()> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn unexpected(&self) -> AssembleErrorReason {
+        AssembleErrorReason::Syntax(match self.peek() {
+            Some(Token::Word(word)) => format!("unexpected {word}"),
+            Some(Token::Number(value)) => format!("unexpected {value}"),
+            Some(Token::Symbol(symbol)) => format!("unexpected {symbol}"),
+            None => "unexpected end of line".to_string(),
+        })
+    }
+
+    /// Takes the next word if it is one of `keywords`, ignoring case.
+    fn keyword(&mut self, keywords: &[&'static str]) -> Option<&'static str> {
+        let Some(Token::Word(word)) = self.peek() else {
+            return None;
+        };
+        let keyword = keywords
+            .iter()
+            .find(|keyword| word.eq_ignore_ascii_case(keyword))?;
+
+        self.position += 1;
+        Some(keyword)
+    }
+
+    fn expression(&mut self) -> AssembleResult<Expression> {
+        let mut expression = self.product()?;
+
+        loop {
+            if self.eat('+') {
+                expression = Expression::Add(Box::new(expression), Box::new(self.product()?));
+            } else if self.eat('-') {
+                expression = Expression::Subtract(Box::new(expression), Box::new(self.product()?));
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn product(&mut self) -> AssembleResult<Expression> {
+        let mut expression = self.factor()?;
+
+        loop {
+            if self.eat('*') {
+                expression = Expression::Multiply(Box::new(expression), Box::new(self.factor()?));
+            } else if self.eat('/') {
+                expression = Expression::Divide(Box::new(expression), Box::new(self.factor()?));
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn factor(&mut self) -> AssembleResult<Expression> {
+        if self.eat('-') {
+            return Ok(Expression::Negate(Box::new(self.factor()?)));
+        }
+
+        if self.eat('+') {
+            return self.factor();
+        }
+
+        if self.eat('(') {
+            let expression = self.expression()?;
+            self.expect(')')?;
+            return Ok(expression);
+        }
+
+        match self.peek().cloned() {
+            Some(Token::Number(value)) => {
+                self.position += 1;
+                Ok(Expression::Number(value))
+            }
+            Some(Token::Word(word)) => {
+                self.position += 1;
+
+                Ok(if word == "$" {
+                    Expression::Here
+                } else if let Some(register) = register(&word) {
+                    Expression::Register(register)
+                } else {
+                    Expression::Label(qualified(&word, self.scope))
+                })
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn operand(&mut self) -> AssembleResult<WrittenOperand> {
+        let mut size = None;
+        let mut distance = None;
+
+        while let Some(keyword) = self.keyword(&["byte", "word", "dword", "short", "near", "far"]) {
+            match keyword {
+                "byte" => size = Some(Size::Byte),
+                "word" => size = Some(Size::Word),
+                "dword" => size = Some(Size::DoubleWord),
+                "short" => distance = Some(Distance::Short),
+                "near" => distance = Some(Distance::Near),
+                _ => distance = Some(Distance::Far),
+            }
+        }
+
+        let is_last = |parser: &Self| {
+            parser.position + 1 >= parser.tokens.len()
+                || parser.tokens[parser.position + 1] == Token::Symbol(',')
+        };
+
+        let value = match self.peek().cloned() {
+            Some(Token::Word(word)) if segment_register(&word).is_some() => {
+                let segment = segment_register(&word);
+                self.position += 1;
+
+                if self.eat(':') {
+                    self.memory(segment)?
+                } else {
+                    Written::SegmentRegister(segment.unwrap())
+                }
+            }
+            Some(Token::Word(word)) if register(&word).is_some() && is_last(self) => {
+                self.position += 1;
+                Written::Register(register(&word).unwrap())
+            }
+            Some(Token::Symbol('[')) => self.memory(None)?,
+            _ => {
+                let value = self.expression()?;
+
+                if self.eat(':') {
+                    Written::FarPointer(value, self.expression()?)
+                } else {
+                    Written::Immediate(value)
+                }
+            }
+        };
+
+        Ok(WrittenOperand {
+            size,
+            distance,
+            value,
+        })
+    }
+
+    fn memory(&mut self, mut segment: Option<SegmentRegister>) -> AssembleResult<Written> {
+        self.expect('[')?;
+
+        let mut displacement_size = None;
+        let mut is_nosplit = false;
+
+        while let Some(keyword) = self.keyword(&["byte", "word", "dword", "nosplit"]) {
+            match keyword {
+                "byte" => displacement_size = Some(Size::Byte),
+                "word" => displacement_size = Some(Size::Word),
+                "dword" => displacement_size = Some(Size::DoubleWord),
+                _ => is_nosplit = true,
+            }
+        }
+
+        if let Some(Token::Word(word)) = self.peek() {
+            if let Some(register) = segment_register(word) {
+                if self.tokens.get(self.position + 1) == Some(&Token::Symbol(':')) {
+                    segment = Some(register);
+                    self.position += 2;
+                }
+            }
+        }
+
+        let address = self.expression()?;
+        self.expect(']')?;
+
+        Ok(Written::Memory {
+            segment,
+            displacement_size,
+            is_nosplit,
+            address,
+        })
+    }
+
+    fn source(&mut self) -> AssembleResult<Source> {
+        let mut prefixes = Prefixes::default();
+
+        loop {
+            if let Some(Token::Word(word)) = self.peek() {
+                // A segment register written ahead of the mnemonic overrides the segment of
+                // whatever the instruction addresses, implicitly or not.
+                if let Some(register) = segment_register(word) {
+                    prefixes.segment_override = Some(register);
+                    self.position += 1;
+                    continue;
+                }
+            }
+
+            let Some(prefix) = self.keyword(&[
+                "lock", "rep", "repe", "repz", "repne", "repnz", "o32", "a32",
+            ]) else {
+                break;
+            };
+
+            match prefix {
+                "lock" => prefixes.lock = true,
+                "rep" | "repe" | "repz" => prefixes.repeat = Some(Repeat::WhileZero),
+                "repne" | "repnz" => prefixes.repeat = Some(Repeat::WhileNotZero),
+                "o32" => prefixes.operand_size_override = true,
+                _ => prefixes.address_size_override = true,
+            }
+        }
+
+        let Some(Token::Word(mnemonic)) = self.next() else {
+            self.position -= 1;
+            return Err(self.unexpected());
+        };
+
+        let mut operands = Vec::new();
+
+        if !self.is_done() {
+            operands.push(self.operand()?);
+
+            while self.eat(',') {
+                operands.push(self.operand()?);
+            }
+        }
+
+        if !self.is_done() {
+            return Err(self.unexpected());
+        }
+
+        Ok(Source {
+            mnemonic: mnemonic.to_ascii_lowercase(),
+            prefixes,
+            operands,
+        })
+    }
+}
+
+/// The full name of a label, putting those starting with a dot under the label before them.
+fn qualified(name: &str, scope: &str) -> String {
+    if name.starts_with('.') {
+        format!("{scope}{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+struct Program {
+    statements: Vec<Statement>,
+    defined: HashSet<String>,
+}
+
+impl Program {
+    fn parse(source: &str) -> Result<Self, AssembleError> {
+        let mut statements = Vec::new();
+        let mut defined = HashSet::new();
+        let mut scope = String::new();
+        let mut model = CpuModel::Intel80386;
+
+        for (index, text) in source.lines().enumerate() {
+            let line = index + 1;
+            let error = |reason| AssembleError { line, reason };
+            let tokens = tokenize(text).map_err(error)?;
+
+            let label = match tokens.as_slice() {
+                [Token::Word(name), Token::Symbol(':'), ..]
+                    if segment_register(name).is_none() && register(name).is_none() =>
+                {
+                    if !name.starts_with('.') {
+                        scope = name.clone();
+                    }
+
+                    let label = qualified(name, &scope);
+
+                    if !defined.insert(label.clone()) {
+                        return Err(error(AssembleErrorReason::DuplicateLabel(label)));
+                    }
+
+                    Some(label)
+                }
+                _ => None,
+            };
+
+            let mut parser = Parser {
+                tokens: &tokens,
+                position: if label.is_some() { 2 } else { 0 },
+                scope: &scope,
+            };
+
+            let body = if parser.is_done() {
+                None
+            } else if parser.keyword(&["bits"]).is_some() {
+                match parser.next() {
+                    Some(Token::Number(16)) if parser.is_done() => None,
+                    _ => {
+                        return Err(error(AssembleErrorReason::Syntax(
+                            "only bits 16 is supported".to_string(),
+                        )))
+                    }
+                }
+            } else if parser.keyword(&["cpu"]).is_some() {
+                model = match parser.next() {
+                    Some(Token::Number(8086)) => CpuModel::Intel8086,
+                    Some(Token::Number(186)) => CpuModel::Intel80186,
+                    Some(Token::Number(386)) => CpuModel::Intel80386,
+                    _ => {
+                        return Err(error(AssembleErrorReason::Syntax(
+                            "cpu has to be 8086, 186 or 386".to_string(),
+                        )))
+                    }
+                };
+
+                None
+            } else if parser.keyword(&["db"]).is_some() {
+                let mut values = vec![parser.expression().map_err(error)?];
+
+                while parser.eat(',') {
+                    values.push(parser.expression().map_err(error)?);
+                }
+
+                if !parser.is_done() {
+                    return Err(error(parser.unexpected()));
+                }
+
+                Some(Body::Data(values))
+            } else {
+                Some(Body::Instruction(parser.source().map_err(error)?))
+            };
+
+            statements.push(Statement {
+                line,
+                label,
+                body,
+                model,
+            });
+        }
+
+        Ok(Program {
+            statements,
+            defined,
+        })
+    }
+
+    /// Lays the program out until no label moves between two passes. Encodings only ever grow
+    /// from one pass to the next, so that branches can't flip between their forms forever.
+    fn assemble(&self) -> Result<Vec<u8>, AssembleError> {
+        let mut previous = HashMap::new();
+        let mut lengths = vec![0; self.statements.len()];
+
+        for _ in 0..MAX_PASSES {
+            let mut output = Vec::new();
+            let mut placed = HashMap::new();
+
+            for (statement, length) in self.statements.iter().zip(&mut lengths) {
+                let here = output.len() as i64;
+
+                if let Some(label) = &statement.label {
+                    placed.insert(label.clone(), here);
+                }
+
+                let context = Context {
+                    here,
+                    placed: &placed,
+                    previous: &previous,
+                    defined: &self.defined,
+                };
+                let bytes =
+                    statement
+                        .assemble(&context, *length)
+                        .map_err(|reason| AssembleError {
+                            line: statement.line,
+                            reason,
+                        })?;
+
+                *length = bytes.len();
+                output.extend(bytes);
+            }
+
+            if placed == previous {
+                return Ok(output);
+            }
+
+            previous = placed;
+        }
+
+        Err(AssembleError {
+            line: self.statements.last().map_or(0, |statement| statement.line),
+            reason: AssembleErrorReason::Unsettled,
+        })
+    }
+}
+
+impl Statement {
+    /// Assembles the statement no shorter than `length`, the size it took on the last pass.
+    fn assemble(&self, context: &Context, length: usize) -> AssembleResult<Vec<u8>> {
+        match &self.body {
+            None => Ok(Vec::new()),
+            Some(Body::Data(values)) => values
+                .iter()
+                .map(|value| {
+                    let value = value.constant(context)?;
+
+                    i8::try_from(value)
+                        .map(|value| value as u8)
+                        .or_else(|_| u8::try_from(value))
+                        .map_err(|_| AssembleErrorReason::OutOfRange(value))
+                })
+                .collect(),
+            Some(Body::Instruction(source)) => {
+                let (instruction, immediate_size) = source.instruction(context)?;
+                let bytes = self.encode(&instruction, immediate_size)?;
+
+                // Branches settle on the near form once they needed it.
+                if bytes.len() < length && immediate_size.is_none() {
+                    let full = if instruction.prefixes.operand_size_override {
+                        4
+                    } else {
+                        2
+                    };
+
+                    if let Ok(longer) = self.encode(&instruction, Some(full)) {
+                        return Ok(longer);
+                    }
+                }
+
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Encodes with the shortest encoding the selected model knows. xchg and test are the same
+    /// whichever way around their operands are written, which the table lists in one order.
+    fn encode(
+        &self,
+        instruction: &Instruction,
+        immediate_size: Option<usize>,
+    ) -> AssembleResult<Vec<u8>> {
+        let needs_later_model = self.model < CpuModel::Intel80386
+            && (instruction.prefixes.operand_size_override
+                || instruction.prefixes.address_size_override);
+
+        if needs_later_model {
+            return Err(AssembleErrorReason::UnsupportedByModel);
+        }
+
+        let mut written = instruction.encode_for(self.model, immediate_size);
+
+        if matches!(instruction.op, Op::Xchg | Op::Test) {
+            let mut swapped = *instruction;
+            swapped.operands.swap(0, 1);
+
+            if let Ok(bytes) = swapped.encode_for(self.model, immediate_size) {
+                if written
+                    .as_ref()
+                    .map_or(true, |written| bytes.len() < written.len())
+                {
+                    written = Ok(bytes);
+                }
+            }
+        }
+
+        written.map_err(|error| {
+            let is_newer = instruction
+                .encode_for(CpuModel::Intel80386, immediate_size)
+                .is_ok();
+
+            if is_newer {
+                AssembleErrorReason::UnsupportedByModel
+            } else {
+                AssembleErrorReason::Encode(error)
+            }
+        })
+    }
+}
+
+/// The operation a mnemonic names, and the width string operations spell with their suffix.
+fn operation(mnemonic: &str) -> Option<(Op, Option<Size>)> {
+    let mnemonic = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == mnemonic)
+        .map_or(mnemonic, |&(_, canonical)| canonical);
+    let ops = || ENCODINGS.iter().map(|spec| spec.op);
+
+    if let Some(op) = ops().find(|op| {
+        !op.is_string() && !matches!(op, Op::JmpFar | Op::CallFar) && op.mnemonic() == mnemonic
+    }) {
+        return Some((op, None));
+    }
+
+    let (stem, size) = match mnemonic.split_at(mnemonic.len().checked_sub(1)?) {
+        (stem, "b") => (stem, Size::Byte),
+        (stem, "w") => (stem, Size::Word),
+        _ => return None,
+    };
+
+    ops()
+        .find(|op| op.is_string() && op.mnemonic() == stem)
+        .map(|op| (op, Some(size)))
+}
+
+fn is_shift(op: Op) -> bool {
+    matches!(
+        op,
+        Op::Shl | Op::Shr | Op::Sar | Op::Rol | Op::Ror | Op::Rcl | Op::Rcr
+    )
+}
+
+/// Whether the immediate of the operation fills a field as wide as the operation, rather than
+/// one of its own, like a port number or a shift count.
+fn has_sized_immediate(op: Op) -> bool {
+    op.sizes_immediate_into_memory() || matches!(op, Op::Push | Op::Imul)
+}
+
+impl Source {
+    /// Builds the instruction the source describes at `context`, along with the size of its
+    /// immediate when a keyword fixes it.
+    fn instruction(&self, context: &Context) -> AssembleResult<(Instruction, Option<usize>)> {
+        let (mut op, suffix) = operation(&self.mnemonic)
+            .ok_or_else(|| AssembleErrorReason::UnknownMnemonic(self.mnemonic.clone()))?;
+
+        let is_far = self.operands.iter().any(|operand| {
+            operand.distance == Some(Distance::Far)
+                || matches!(operand.value, Written::FarPointer(..))
+        });
+
+        op = match op {
+            Op::Jmp if is_far => Op::JmpFar,
+            Op::Call if is_far => Op::CallFar,
+            op => op,
+        };
+
+        let size = suffix.or(self.size(op)?);
+        let mut prefixes = self.prefixes;
+
+        if size == Some(Size::DoubleWord) {
+            prefixes.operand_size_override = true;
+        }
+
+        let is_wide = size != Some(Size::Byte);
+        let width = if prefixes.is_double_word(is_wide) {
+            Size::DoubleWord
+        } else if is_wide {
+            Size::Word
+        } else {
+            Size::Byte
+        };
+        let mut operands = [None; 3];
+
+        for (index, operand) in self.operands.iter().enumerate() {
+            let value = match &operand.value {
+                Written::Register(register) => Operand::Register(*register),
+                Written::SegmentRegister(register) => Operand::SegmentRegister(*register),
+                Written::Memory {
+                    segment,
+                    displacement_size,
+                    is_nosplit,
+                    address,
+                } => {
+                    if segment.is_some() {
+                        prefixes.segment_override = *segment;
+                    }
+
+                    let address = address.evaluate(context)?;
+                    let address = effective_address(address, *displacement_size, *is_nosplit)?;
+
+                    if matches!(address, EffectiveAddress::Extended(_)) {
+                        prefixes.address_size_override = true;
+                    }
+
+                    Operand::Memory(address)
+                }
+                Written::Immediate(value) if op.is_relative_branch() => {
+                    let displacement = value.constant(context)? - context.here;
+
+                    Operand::Immediate(relative(displacement)?)
+                }
+                Written::Immediate(value) => {
+                    let value = value.constant(context)?;
+
+                    Operand::Immediate(if has_sized_immediate(op) {
+                        sized_immediate(value, width)?
+                    } else {
+                        immediate(value)?
+                    })
+                }
+                Written::FarPointer(segment, offset) => {
+                    let segment = segment.constant(context)?;
+                    let offset = offset.constant(context)?;
+                    let segment = u16::try_from(segment)
+                        .map_err(|_| AssembleErrorReason::OutOfRange(segment))?;
+                    let offset = if prefixes.operand_size_override {
+                        u32::try_from(offset).ok()
+                    } else {
+                        word(offset).map(u32::from)
+                    }
+                    .ok_or(AssembleErrorReason::OutOfRange(offset))?;
+
+                    Operand::FarPointer(segment, offset)
+                }
+            };
+
+            *operands
+                .get_mut(index)
+                .ok_or_else(|| AssembleErrorReason::Syntax("too many operands".to_string()))? =
+                Some(value);
+        }
+
+        // The base of aam and aad is implied when it is 10.
+        if matches!(op, Op::Aam | Op::Aad) && operands[0].is_none() {
+            operands[0] = Some(Operand::Immediate(ImmediateValue::SignedWord(10)));
+        }
+
+        let instruction = Instruction {
+            op,
+            prefixes,
+            operands,
+            is_wide,
+            encoding: Encoding::default(),
+        };
+
+        Ok((instruction, self.immediate_size(op, prefixes)))
+    }
+
+    /// The width of the operation, from its registers or else from its size keywords.
+    fn size(&self, op: Op) -> AssembleResult<Option<Size>> {
+        // The port of in and out and the count of shifts have a width of their own.
+        let sized = match op {
+            Op::Out => self.operands.get(1..).unwrap_or_default(),
+            Op::In => &self.operands[..self.operands.len().min(1)],
+            op if is_shift(op) => &self.operands[..self.operands.len().min(1)],
+            _ => &self.operands,
+        };
+
+        let from_register = sized.iter().find_map(|operand| match operand.value {
+            Written::Register(register) => Some(Size::of(register)),
+            _ => None,
+        });
+        let from_keyword = sized.iter().find_map(|operand| match operand.value {
+            // A byte or word pushed is the size of the immediate, not of the push.
+            Written::Immediate(_) if op == Op::Push && operand.size != Some(Size::DoubleWord) => {
+                None
+            }
+            _ => operand.size,
+        });
+
+        let size = from_register.or(from_keyword);
+        let is_memory = |operand: &WrittenOperand| matches!(operand.value, Written::Memory { .. });
+        let is_immediate =
+            |operand: &WrittenOperand| matches!(operand.value, Written::Immediate(_));
+
+        let needs_size = self.operands.iter().any(is_memory)
+            && (op.sizes_memory_operand()
+                || (op.sizes_immediate_into_memory() && self.operands.iter().any(is_immediate)));
+
+        if size.is_none() && needs_size {
+            return Err(AssembleErrorReason::UnknownSize);
+        }
+
+        Ok(size)
+    }
+
+    /// How many bytes the immediate of a push or the displacement of a branch takes when a
+    /// keyword spells it out.
+    fn immediate_size(&self, op: Op, prefixes: Prefixes) -> Option<usize> {
+        let operand = self.operands.first()?;
+
+        if !matches!(operand.value, Written::Immediate(_)) {
+            return None;
+        }
+
+        let full = if prefixes.operand_size_override { 4 } else { 2 };
+
+        match (operand.distance, operand.size) {
+            _ if !(op == Op::Push || op.is_relative_branch()) => None,
+            (Some(Distance::Short), _) | (_, Some(Size::Byte)) => Some(1),
+            (Some(Distance::Near), _) | (_, Some(Size::Word | Size::DoubleWord)) => Some(full),
+            _ => None,
+        }
+    }
+}
+
+/// A word written either signed or unsigned.
+fn word(value: i64) -> Option<u16> {
+    i16::try_from(value)
+        .map(|value| value as u16)
+        .or_else(|_| u16::try_from(value))
+        .ok()
+}
+
+/// An immediate in a field of its own, which the encoder checks the range of.
+fn immediate(value: i64) -> AssembleResult<ImmediateValue> {
+    if let Ok(value) = i8::try_from(value) {
+        Ok(ImmediateValue::SignedByte(value))
+    } else if let Ok(value) = i16::try_from(value) {
+        Ok(ImmediateValue::SignedWord(value))
+    } else {
+        i32::try_from(value)
+            .map(ImmediateValue::SignedDoubleWord)
+            .map_err(|_| AssembleErrorReason::OutOfRange(value))
+    }
+}
+
+/// An immediate as wide as the operation, written either signed or unsigned.
+fn sized_immediate(value: i64, size: Size) -> AssembleResult<ImmediateValue> {
+    let out_of_range = |_| AssembleErrorReason::OutOfRange(value);
+
+    Ok(match size {
+        Size::Byte => ImmediateValue::SignedByte(
+            i8::try_from(value)
+                .or_else(|_| u8::try_from(value).map(|value| value as i8))
+                .map_err(out_of_range)?,
+        ),
+        Size::Word => ImmediateValue::SignedWord(
+            word(value).ok_or(AssembleErrorReason::OutOfRange(value))? as i16,
+        ),
+        Size::DoubleWord => ImmediateValue::SignedDoubleWord(
+            i32::try_from(value)
+                .or_else(|_| u32::try_from(value).map(|value| value as i32))
+                .map_err(out_of_range)?,
+        ),
+    })
+}
+
+/// The displacement of a branch landing `distance` bytes past the start of the instruction,
+/// counted from the end of the 3 or 6 byte near form the encoder measures it against.
+fn relative(distance: i64) -> AssembleResult<ImmediateValue> {
+    if let Ok(displacement) = i16::try_from(distance - 3) {
+        return Ok(ImmediateValue::SignedWord(displacement));
+    }
+
+    i32::try_from(distance - 6)
+        .map(ImmediateValue::SignedDoubleWord)
+        .map_err(|_| AssembleErrorReason::OutOfRange(distance))
+}
+
+/// The address the registers and displacement of `sum` make up, in the 32-bit forms when a
+/// register of the address or a `dword` displacement without one calls for them.
+fn effective_address(
+    sum: Sum,
+    displacement_size: Option<Size>,
+    is_nosplit: bool,
+) -> AssembleResult<EffectiveAddress> {
+    let terms: Vec<Term> = sum
+        .terms
+        .into_iter()
+        .filter(|term| term.scale != 0)
+        .collect();
+    let displacement = sum.constant;
+
+    let is_extended = match terms.first() {
+        Some(term) => term.register.is_extended(),
+        None => displacement_size == Some(Size::DoubleWord),
+    };
+
+    if terms
+        .iter()
+        .any(|term| term.register.is_extended() != is_extended)
+    {
+        return Err(AssembleErrorReason::Syntax(
+            "16-bit and 32-bit registers mixed in an address".to_string(),
+        ));
+    }
+
+    if is_extended {
+        return extended_address(terms, displacement, displacement_size, is_nosplit)
+            .map(EffectiveAddress::Extended);
+    }
+
+    let invalid = || AssembleErrorReason::Syntax("invalid 16-bit address".to_string());
+
+    if terms.iter().any(|term| term.scale != 1) {
+        return Err(invalid());
+    }
+
+    let offset = word(displacement).ok_or(AssembleErrorReason::OutOfRange(displacement))? as i16;
+    let byte = i8::try_from(offset);
+
+    Ok(match (terms.as_slice(), byte) {
+        ([], _) => EffectiveAddress::DirectAddress(offset as u16),
+        ([base], _) if offset == 0 => EffectiveAddress::Register(base.register),
+        ([base], Ok(byte)) => EffectiveAddress::RegisterPlusByte(base.register, byte),
+        ([base], Err(_)) => EffectiveAddress::RegisterPlusWord(base.register, offset),
+        ([base, index], _) if offset == 0 => {
+            EffectiveAddress::RegisterSum(base.register, index.register)
+        }
+        ([base, index], Ok(byte)) => {
+            EffectiveAddress::RegisterSumPlusByte(base.register, index.register, byte)
+        }
+        ([base, index], Err(_)) => {
+            EffectiveAddress::RegisterSumPlusWord(base.register, index.register, offset)
+        }
+        _ => return Err(invalid()),
+    })
+}
+
+/// Splits the registers into a base and a scaled index the way nasm does: a register written
+/// with a factor is the index, an index scaled by 1, 2, 3, 5 or 9 alone is split into a base
+/// and an index unless `nosplit` keeps it whole, and esp can only be the base.
+fn extended_address(
+    terms: Vec<Term>,
+    displacement: i64,
+    displacement_size: Option<Size>,
+    is_nosplit: bool,
+) -> AssembleResult<ExtendedAddress> {
+    let invalid = || AssembleErrorReason::Syntax("invalid 32-bit address".to_string());
+
+    let (base, index) = match terms.as_slice() {
+        [] => (None, None),
+        [term] if term.scale == 1 && !(is_nosplit && term.is_scaled) => (Some(term.register), None),
+        [term] if is_nosplit => (None, Some((term.register, term.scale))),
+        [term] if matches!(term.scale, 2 | 3 | 5 | 9) => {
+            (Some(term.register), Some((term.register, term.scale - 1)))
+        }
+        [term] => (None, Some((term.register, term.scale))),
+        [first, second] if first.scale == 1 && !first.is_scaled => {
+            if second.scale == 1 && second.register == Register::Esp {
+                (Some(second.register), Some((first.register, 1)))
+            } else {
+                (Some(first.register), Some((second.register, second.scale)))
+            }
+        }
+        [first, second] if second.scale == 1 && !second.is_scaled => {
+            (Some(second.register), Some((first.register, first.scale)))
+        }
+        _ => return Err(invalid()),
+    };
+
+    let index = match index {
+        Some((register, scale @ (1 | 2 | 4 | 8))) => Some((register, scale as u8)),
+        Some(_) => return Err(invalid()),
+        None => None,
+    };
+
+    let value = i32::try_from(displacement)
+        .or_else(|_| u32::try_from(displacement).map(|value| value as i32))
+        .map_err(|_| AssembleErrorReason::OutOfRange(displacement))?;
+    let byte = i8::try_from(value);
+
+    let displacement = match (displacement_size, byte) {
+        (Some(Size::Byte), Ok(value)) => Displacement::Byte(value),
+        (Some(Size::Byte), Err(_)) => return Err(AssembleErrorReason::OutOfRange(displacement)),
+        (Some(_), _) => Displacement::DoubleWord(value),
+        (None, _) if base.is_none() => Displacement::DoubleWord(value),
+        // Like in the 16-bit forms, no displacement with ebp as the base stands for another
+        // address, so it takes a byte of 0.
+        (None, _) if value == 0 && base != Some(Register::Ebp) => Displacement::None,
+        (None, Ok(value)) => Displacement::Byte(value),
+        (None, Err(_)) => Displacement::DoubleWord(value),
+    };
+
+    Ok(ExtendedAddress {
+        base,
+        index,
+        displacement,
+    })
+}
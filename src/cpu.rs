@@ -0,0 +1,14 @@
+/// The processor a program is decoded and simulated for.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CpuModel {
+    /// The original instruction set. Opcodes the later models repurposed are decoded the way
+    /// the 8086 silicon does, as aliases of their neighbours.
+    #[default]
+    Intel8086,
+    /// The 80186 and 80188, adding push imm, pusha/popa, three operand imul, shifts by an
+    /// immediate count, enter/leave, bound and ins/outs.
+    Intel80186,
+    /// The 80386 in real mode, adding the operand and address size prefixes on top of the
+    /// 80186. The 32-bit operands they select are only decoded, not simulated.
+    Intel80386,
+}
@@ -0,0 +1,8 @@
+use std::io::{Read, Seek};
+
+/// Anything instructions can be decoded from. Decoding peeks ahead at ModRM bytes and the
+/// simulator jumps around in the program, so the source has to be seekable: byte slices
+/// wrapped in a `Cursor` are read in place, while streams like stdin need buffering first.
+pub trait ByteCursor: Read + Seek {}
+
+impl<T: Read + Seek> ByteCursor for T {}
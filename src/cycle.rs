@@ -1,3 +1,17 @@
 pub trait EstimatedCycleCount {
     fn num_cycles(&self) -> u32;
+
+    /// The fewest and most cycles the instruction can take. Only instructions whose timing depends
+    /// on the data they operate on report a wider range than `num_cycles`.
+    fn cycle_range(&self) -> (u32, u32) {
+        let num_cycles = self.num_cycles();
+
+        (num_cycles, num_cycles)
+    }
+}
+
+/// Places a data dependent instruction within its documented cycle range, in proportion to how
+/// many of `num_bits` significant bits are set.
+pub(crate) fn interpolate_cycles((min, max): (u32, u32), set_bits: u32, num_bits: u32) -> u32 {
+    min + (max - min) * set_bits / num_bits
 }
@@ -0,0 +1,171 @@
+use crate::cursor::ByteCursor;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{Read, SeekFrom};
+
+/// Why the bytes at some offset couldn't be decoded into an instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DecodeErrorReason {
+    /// The opcode isn't part of the instruction set of the model being decoded for.
+    UnknownOpcode,
+    /// The source ran out before the instruction was complete.
+    TruncatedInstruction,
+    /// The opcode is known, but the reg field or mode of its ModRM byte isn't valid for it.
+    InvalidModRm,
+    /// Prefixes ran the instruction past the longest one a processor accepts.
+    InstructionTooLong,
+    /// The source failed for a reason other than running out.
+    Io(io::ErrorKind),
+    /// The instruction decodes, but works on 32-bit operands or addresses, which only the
+    /// decoder knows about.
+    NotSimulated,
+}
+
+impl From<io::Error> for DecodeErrorReason {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof => DecodeErrorReason::TruncatedInstruction,
+            kind => DecodeErrorReason::Io(kind),
+        }
+    }
+}
+
+impl Display for DecodeErrorReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeErrorReason::UnknownOpcode => f.write_str("unknown opcode"),
+            DecodeErrorReason::TruncatedInstruction => f.write_str("truncated instruction"),
+            DecodeErrorReason::InvalidModRm => f.write_str("invalid ModRM byte for the opcode"),
+            DecodeErrorReason::InstructionTooLong => f.write_str("instruction too long"),
+            DecodeErrorReason::Io(kind) => f.write_fmt(format_args!("failed to read: {kind}")),
+            DecodeErrorReason::NotSimulated => {
+                f.write_str("32-bit operands and addresses are not simulated")
+            }
+        }
+    }
+}
+
+pub(crate) type DecodeResult<T> = Result<T, DecodeErrorReason>;
+
+/// An instruction that couldn't be decoded, along with where it started and every byte read
+/// for it before giving up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub offset: u64,
+    pub bytes: Vec<u8>,
+    pub reason: DecodeErrorReason,
+}
+
+impl DecodeError {
+    /// Rewinds to the start of the instruction to collect what was read of it, leaving the
+    /// reader where decoding gave up.
+    pub(crate) fn read_back(
+        reader: &mut impl ByteCursor,
+        offset: u64,
+        reason: DecodeErrorReason,
+    ) -> Self {
+        let mut bytes = Vec::new();
+
+        if let Ok(end) = reader.stream_position() {
+            if reader.seek(SeekFrom::Start(offset)).is_ok() {
+                let _ = reader.by_ref().take(end - offset).read_to_end(&mut bytes);
+            }
+        }
+
+        DecodeError {
+            offset,
+            bytes,
+            reason,
+        }
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{} at offset {}:", self.reason, self.offset))?;
+
+        for byte in &self.bytes {
+            f.write_fmt(format_args!(" {byte:02x}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for DecodeError {}
+
+/// An instruction that no encoding in the table can express, like `mov [bx], [si]` or an
+/// immediate too wide for its operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodeError {
+    pub instruction: String,
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("no encoding for {}", self.instruction))
+    }
+}
+
+impl Error for EncodeError {}
+
+/// Why a line of source couldn't be assembled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssembleErrorReason {
+    /// The line isn't a label, directive or instruction the assembler understands.
+    Syntax(String),
+    UnknownMnemonic(String),
+    UndefinedLabel(String),
+    DuplicateLabel(String),
+    /// Neither a register nor a size keyword gives the width of the operation.
+    UnknownSize,
+    /// A value doesn't fit the field it is written into.
+    OutOfRange(i64),
+    /// The instruction needs a later processor than the one the `cpu` directive selects.
+    UnsupportedByModel,
+    /// Labels kept moving between passes, as encodings shrank and grew.
+    Unsettled,
+    Encode(EncodeError),
+}
+
+impl Display for AssembleErrorReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssembleErrorReason::Syntax(message) => f.write_str(message),
+            AssembleErrorReason::UnknownMnemonic(mnemonic) => {
+                f.write_fmt(format_args!("unknown mnemonic {mnemonic}"))
+            }
+            AssembleErrorReason::UndefinedLabel(label) => {
+                f.write_fmt(format_args!("undefined label {label}"))
+            }
+            AssembleErrorReason::DuplicateLabel(label) => {
+                f.write_fmt(format_args!("label {label} defined twice"))
+            }
+            AssembleErrorReason::UnknownSize => f.write_str("operation size not specified"),
+            AssembleErrorReason::OutOfRange(value) => {
+                f.write_fmt(format_args!("{value} is out of range"))
+            }
+            AssembleErrorReason::UnsupportedByModel => {
+                f.write_str("instruction not supported by the selected cpu")
+            }
+            AssembleErrorReason::Unsettled => f.write_str("labels never settled"),
+            AssembleErrorReason::Encode(error) => error.fmt(f),
+        }
+    }
+}
+
+/// A line that couldn't be assembled, counting from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub reason: AssembleErrorReason,
+}
+
+impl Display for AssembleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{} on line {}", self.reason, self.line))
+    }
+}
+
+impl Error for AssembleError {}
@@ -26,6 +26,23 @@ pub enum FlagRegister {
     Overflow,
 }
 
+/// A set of flags, each one held at its bit in the FLAGS register.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FlagMask(u16);
+
+impl FlagMask {
+    /// The flags arithmetic and logical operations update.
+    pub const ARITHMETIC: FlagMask = FlagMask(0b0000_1000_1101_0101);
+
+    pub fn contains(self, flag: FlagRegister) -> bool {
+        self.0 & (1 << flag.to_memory_address()) != 0
+    }
+
+    pub fn without(self, flag: FlagRegister) -> Self {
+        FlagMask(self.0 & !(1 << flag.to_memory_address()))
+    }
+}
+
 use crate::instructions::operands::ArithmeticResult;
 use crate::memory::Memory;
 use FlagRegister::*;
@@ -85,6 +102,44 @@ impl FlagRegisterManager {
         self.get_memory_mut()[flag.to_memory_address() as usize] = false;
     }
 
+    pub fn write_flag(&mut self, flag: FlagRegister, value: bool) {
+        self.get_memory_mut()[flag.to_memory_address() as usize] = value;
+    }
+
+    /// Packs the flags into their FLAGS register layout. The 8086 always reads the reserved
+    /// bit 1 and bits 12 through 15 as set.
+    pub fn read_word(&self) -> u16 {
+        self.get_memory()
+            .iter()
+            .enumerate()
+            .fold(
+                0xF002,
+                |word, (bit, &is_set)| {
+                    if is_set {
+                        word | (1 << bit)
+                    } else {
+                        word
+                    }
+                },
+            )
+    }
+
+    pub fn write_word(&mut self, value: u16) {
+        for flag in [
+            Carry,
+            Parity,
+            AuxiliaryCarry,
+            Zero,
+            Sign,
+            Trap,
+            Interrupt,
+            Direction,
+            Overflow,
+        ] {
+            self.write_flag(flag, value & (1 << flag.to_memory_address()) != 0);
+        }
+    }
+
     pub fn set_flags_on_op(&mut self, result: ArithmeticResult) {
         let ArithmeticResult {
             carry,
@@ -92,45 +147,24 @@ impl FlagRegisterManager {
             zero,
             sign,
             auxiliary_carry,
+            affected_flags,
             ..
         } = result;
 
-        let value: i16 = result.value().into();
-
-        if value.count_ones() % 2 == 0 {
-            self.set_flag(Parity)
-        } else {
-            self.unset_flag(Parity)
-        }
-
-        if carry {
-            self.set_flag(Carry);
-        } else {
-            self.unset_flag(Carry);
-        }
-
-        if overflow {
-            self.set_flag(Overflow);
-        } else {
-            self.unset_flag(Overflow);
-        }
-
-        if zero {
-            self.set_flag(Zero);
-        } else {
-            self.unset_flag(Zero);
-        }
-
-        if sign {
-            self.set_flag(Sign);
-        } else {
-            self.unset_flag(Sign);
-        }
-
-        if auxiliary_carry {
-            self.set_flag(AuxiliaryCarry);
-        } else {
-            self.unset_flag(AuxiliaryCarry);
+        let value: u16 = result.value().into();
+
+        // Parity only ever counts the low byte, even of a word result.
+        for (flag, is_set) in [
+            (Parity, (value & 0xFF).count_ones().is_multiple_of(2)),
+            (Carry, carry),
+            (Overflow, overflow),
+            (Zero, zero),
+            (Sign, sign),
+            (AuxiliaryCarry, auxiliary_carry),
+        ] {
+            if affected_flags.contains(flag) {
+                self.write_flag(flag, is_set);
+            }
         }
     }
 
@@ -0,0 +1,147 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::flag_register::{FlagRegister, FlagRegisterManager};
+use crate::instructions::interrupt::raise_interrupt;
+use crate::instructions::{AnyInstruction, Execute};
+use crate::register::Register;
+use crate::store::Store;
+
+pub enum AdjustInstructions {
+    AsciiAdjustForAdd(AnyInstruction),
+    DecimalAdjustForAdd(AnyInstruction),
+    AsciiAdjustForSubtract(AnyInstruction),
+    DecimalAdjustForSubtract(AnyInstruction),
+    AsciiAdjustForMultiply(AnyInstruction),
+    AsciiAdjustForDivide(AnyInstruction),
+}
+
+impl AdjustInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            AdjustInstructions::AsciiAdjustForAdd(instruction)
+            | AdjustInstructions::DecimalAdjustForAdd(instruction)
+            | AdjustInstructions::AsciiAdjustForSubtract(instruction)
+            | AdjustInstructions::DecimalAdjustForSubtract(instruction)
+            | AdjustInstructions::AsciiAdjustForMultiply(instruction)
+            | AdjustInstructions::AsciiAdjustForDivide(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            AdjustInstructions::AsciiAdjustForAdd(instruction)
+            | AdjustInstructions::DecimalAdjustForAdd(instruction)
+            | AdjustInstructions::AsciiAdjustForSubtract(instruction)
+            | AdjustInstructions::DecimalAdjustForSubtract(instruction)
+            | AdjustInstructions::AsciiAdjustForMultiply(instruction)
+            | AdjustInstructions::AsciiAdjustForDivide(instruction) => instruction,
+        }
+    }
+}
+
+impl EstimatedCycleCount for AdjustInstructions {
+    fn num_cycles(&self) -> u32 {
+        match self {
+            AdjustInstructions::AsciiAdjustForAdd(_)
+            | AdjustInstructions::DecimalAdjustForAdd(_)
+            | AdjustInstructions::AsciiAdjustForSubtract(_)
+            | AdjustInstructions::DecimalAdjustForSubtract(_) => 4,
+            AdjustInstructions::AsciiAdjustForMultiply(_) => 83,
+            AdjustInstructions::AsciiAdjustForDivide(_) => 60,
+        }
+    }
+}
+
+/// Sets the flags the adjustments leave defined from the adjusted value of al.
+fn set_flags_on_result(flags: &mut FlagRegisterManager, al: u8) {
+    flags.write_flag(FlagRegister::Zero, al == 0);
+    flags.write_flag(FlagRegister::Sign, al & 0x80 != 0);
+    flags.write_flag(FlagRegister::Parity, al.count_ones().is_multiple_of(2));
+}
+
+impl Execute for AdjustInstructions {
+    fn execute(&self, reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        use FlagRegister::*;
+
+        let al = store.register_store().read_byte_from_register(Register::Al);
+        let ah = store.register_store().read_byte_from_register(Register::Ah);
+        let carry = store.flag_register_store().get_flag(Carry);
+        let auxiliary_carry = store.flag_register_store().get_flag(AuxiliaryCarry);
+
+        // The low nibble needs adjusting if it went past 9 or if the last operation carried out
+        // of it, which is what AF records.
+        let adjusts_low_nibble = (al & 0xF) > 9 || auxiliary_carry;
+
+        let (al, ah) = match self {
+            AdjustInstructions::AsciiAdjustForAdd(_)
+            | AdjustInstructions::AsciiAdjustForSubtract(_) => {
+                let is_addition = matches!(self, AdjustInstructions::AsciiAdjustForAdd(_));
+
+                let (al, ah) = match (adjusts_low_nibble, is_addition) {
+                    (false, _) => (al, ah),
+                    (true, true) => (al.wrapping_add(6), ah.wrapping_add(1)),
+                    (true, false) => (al.wrapping_sub(6), ah.wrapping_sub(1)),
+                };
+
+                let flags = store.flag_register_store_mut();
+                flags.write_flag(AuxiliaryCarry, adjusts_low_nibble);
+                flags.write_flag(Carry, adjusts_low_nibble);
+
+                (al & 0xF, ah)
+            }
+            AdjustInstructions::DecimalAdjustForAdd(_)
+            | AdjustInstructions::DecimalAdjustForSubtract(_) => {
+                let is_addition = matches!(self, AdjustInstructions::DecimalAdjustForAdd(_));
+                let adjusts_high_nibble = al > 0x99 || carry;
+
+                let adjustment = if adjusts_low_nibble { 0x06 } else { 0 }
+                    + if adjusts_high_nibble { 0x60 } else { 0 };
+
+                let adjusted = if is_addition {
+                    al.wrapping_add(adjustment)
+                } else {
+                    al.wrapping_sub(adjustment)
+                };
+
+                let flags = store.flag_register_store_mut();
+                flags.write_flag(AuxiliaryCarry, adjusts_low_nibble);
+                flags.write_flag(Carry, adjusts_high_nibble);
+                set_flags_on_result(flags, adjusted);
+
+                (adjusted, ah)
+            }
+            AdjustInstructions::AsciiAdjustForMultiply(AnyInstruction { destination, .. }) => {
+                let (base, _) = destination.to_immediate_value(true, store);
+                let base: u16 = base.into();
+                let base = base as u8;
+
+                // aam divides by its base, so a zero base faults like div does.
+                if base == 0 {
+                    raise_interrupt(reader, store, 0);
+
+                    return self.num_cycles();
+                }
+
+                let (al, ah) = (al % base, al / base);
+                set_flags_on_result(store.flag_register_store_mut(), al);
+
+                (al, ah)
+            }
+            AdjustInstructions::AsciiAdjustForDivide(AnyInstruction { destination, .. }) => {
+                let (base, _) = destination.to_immediate_value(true, store);
+                let base: u16 = base.into();
+
+                let al = ah.wrapping_mul(base as u8).wrapping_add(al);
+                set_flags_on_result(store.flag_register_store_mut(), al);
+
+                (al, 0)
+            }
+        };
+
+        let registers = store.register_store_mut();
+        registers.write_byte_to_register(Register::Al, al);
+        registers.write_byte_to_register(Register::Ah, ah);
+
+        self.num_cycles()
+    }
+}
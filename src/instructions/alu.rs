@@ -0,0 +1,99 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::flag_register::FlagRegister;
+use crate::instructions::generic::Op;
+use crate::instructions::operands::Operand;
+use crate::instructions::{AnyInstruction, Execute};
+use crate::store::Store;
+
+/// The arithmetic and logical operations on a destination and a source: add, adc, sub, sbb,
+/// cmp, and, or, xor and test. They only differ in what they compute and whether they write it
+/// back, rather than just setting the flags by it.
+pub struct AluInstruction(pub Op, pub AnyInstruction);
+
+impl AluInstruction {
+    /// Whether the result only goes into the flags, as for cmp and test.
+    fn writes_back(&self) -> bool {
+        !matches!(self.0, Op::Cmp | Op::Test)
+    }
+}
+
+impl EstimatedCycleCount for AluInstruction {
+    fn num_cycles(&self) -> u32 {
+        use Operand::*;
+
+        let AluInstruction(op, instruction) = self;
+
+        match (instruction.destination, instruction.source.unwrap()) {
+            (Register(_), Register(_)) => 3,
+            (Register(_), Memory(ea)) => 9 + ea.num_cycles(),
+            (Memory(ea), Register(_)) if self.writes_back() => 16 + ea.num_cycles(),
+            (Memory(ea), Register(_)) => 9 + ea.num_cycles(),
+            (Accumulator | AccumulatorWide, Immediate(_)) => 4,
+            (Register(_), Immediate(_)) if *op == Op::Test => 5,
+            (Register(_), Immediate(_)) => 4,
+            (Memory(ea), Immediate(_)) => {
+                let base = match op {
+                    Op::Cmp => 10,
+                    Op::Test => 11,
+                    _ => 17,
+                };
+
+                base + ea.num_cycles()
+            }
+            _ => panic!("Invalid {} operation", op.mnemonic()),
+        }
+    }
+}
+
+impl Execute for AluInstruction {
+    fn execute(&self, _reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        let AluInstruction(
+            op,
+            AnyInstruction {
+                source,
+                destination,
+                is_wide,
+                ..
+            },
+        ) = self;
+
+        let (rhs, clock_penalty_rhs) = source
+            .as_ref()
+            .map(|source| source.to_immediate_value(*is_wide, store))
+            .unwrap_or_else(|| {
+                panic!(
+                    "{} operation expects both source and destination",
+                    op.mnemonic()
+                )
+            });
+
+        let (lhs, clock_penalty_lhs) = destination.to_immediate_value(*is_wide, store);
+
+        let carry = store.flag_register_store().get_flag(FlagRegister::Carry);
+        let op_result = match op {
+            Op::Add => lhs + rhs,
+            Op::Adc => lhs.add_with_carry(rhs, carry),
+            Op::Sub | Op::Cmp => lhs - rhs,
+            Op::Sbb => lhs.subtract_with_borrow(rhs, carry),
+            Op::And | Op::Test => lhs & rhs,
+            Op::Or => lhs | rhs,
+            Op::Xor => lhs ^ rhs,
+            _ => panic!(
+                "{} is not an arithmetic or logical operation",
+                op.mnemonic()
+            ),
+        };
+
+        store.flag_register_store_mut().set_flags_on_op(op_result);
+
+        if self.writes_back() {
+            destination.write_value(op_result.value(), *is_wide, store);
+        }
+
+        let clock_penalty =
+            if clock_penalty_rhs { 4 } else { 0 } + if clock_penalty_lhs { 4 } else { 0 };
+
+        self.num_cycles() + clock_penalty
+    }
+}
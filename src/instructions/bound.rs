@@ -0,0 +1,47 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::instructions::interrupt::raise_interrupt;
+use crate::instructions::operands::Operand;
+use crate::instructions::{AnyInstruction, Execute};
+use crate::store::Store;
+
+pub struct BoundInstruction(pub AnyInstruction);
+
+impl EstimatedCycleCount for BoundInstruction {
+    /// Indices within bounds take the fewest cycles of the documented 33 to 35.
+    fn num_cycles(&self) -> u32 {
+        33
+    }
+
+    fn cycle_range(&self) -> (u32, u32) {
+        (33, 35)
+    }
+}
+
+impl Execute for BoundInstruction {
+    /// The 80186 pushes the address of the bound itself, so a handler can fix the index and
+    /// retry. The simulator only knows where the next instruction starts, so handlers resume
+    /// after it instead.
+    fn execute(&self, reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        let Some(Operand::Memory(address)) = self.0.source else {
+            panic!("Invalid BOUND operation");
+        };
+
+        let (index, _) = self.0.destination.to_immediate_value(true, store);
+        let index: i16 = index.into();
+
+        // The pair of bounds is laid out like a far pointer, the lower bound first.
+        let ((upper, lower), clock_penalty) =
+            store.read_far_pointer_from_effective_memory_address(address);
+
+        let num_cycles = if index < lower as i16 || index > upper as i16 {
+            raise_interrupt(reader, store, 5);
+
+            35
+        } else {
+            self.num_cycles()
+        };
+
+        num_cycles + if clock_penalty { 4 } else { 0 }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::instructions::jump::far_offset;
+use crate::instructions::operands::Operand;
+use crate::instructions::{instruction_pointer, set_instruction_pointer, AnyInstruction, Execute};
+use crate::segment_register::SegmentRegister;
+use crate::store::Store;
+
+pub enum CallInstructions {
+    DirectWithinSegment(AnyInstruction),
+    IndirectWithinSegment(AnyInstruction),
+    DirectIntersegment(AnyInstruction),
+    IndirectIntersegment(AnyInstruction),
+}
+
+impl CallInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            CallInstructions::DirectWithinSegment(instruction)
+            | CallInstructions::IndirectWithinSegment(instruction)
+            | CallInstructions::DirectIntersegment(instruction)
+            | CallInstructions::IndirectIntersegment(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            CallInstructions::DirectWithinSegment(instruction)
+            | CallInstructions::IndirectWithinSegment(instruction)
+            | CallInstructions::DirectIntersegment(instruction)
+            | CallInstructions::IndirectIntersegment(instruction) => instruction,
+        }
+    }
+}
+
+impl EstimatedCycleCount for CallInstructions {
+    fn num_cycles(&self) -> u32 {
+        match self {
+            CallInstructions::DirectWithinSegment(_) => 19,
+            CallInstructions::IndirectWithinSegment(instruction) => match instruction.destination {
+                Operand::Register(_) => 16,
+                Operand::Memory(ea) => 21 + ea.num_cycles(),
+                _ => panic!("Invalid call operation"),
+            },
+            CallInstructions::DirectIntersegment(_) => 28,
+            CallInstructions::IndirectIntersegment(instruction) => match instruction.destination {
+                Operand::Memory(ea) => 37 + ea.num_cycles(),
+                _ => panic!("Invalid call operation"),
+            },
+        }
+    }
+}
+
+impl Execute for CallInstructions {
+    fn execute(&self, reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        let return_address = instruction_pointer(reader);
+
+        let (target_segment, target_offset, clock_penalty) = match self {
+            CallInstructions::DirectWithinSegment(AnyInstruction { destination, .. }) => {
+                let (displacement, _) = destination.to_immediate_value(true, store);
+                let displacement: u16 = displacement.into();
+
+                (None, return_address.wrapping_add(displacement), false)
+            }
+            CallInstructions::IndirectWithinSegment(AnyInstruction { destination, .. }) => {
+                let (target, clock_penalty) = destination.to_immediate_value(true, store);
+
+                (None, target.into(), clock_penalty)
+            }
+            CallInstructions::DirectIntersegment(AnyInstruction { destination, .. }) => {
+                let Operand::FarPointer(segment, offset) = *destination else {
+                    panic!("Direct intersegment call expects a far pointer")
+                };
+
+                (Some(segment), far_offset(offset), false)
+            }
+            CallInstructions::IndirectIntersegment(AnyInstruction { destination, .. }) => {
+                let Operand::Memory(address) = *destination else {
+                    panic!("Indirect intersegment call expects a memory operand")
+                };
+
+                let ((segment, offset), clock_penalty) =
+                    store.read_far_pointer_from_effective_memory_address(address);
+
+                (Some(segment), offset, clock_penalty)
+            }
+        };
+
+        if let Some(segment) = target_segment {
+            let cs = store
+                .segment_register_store()
+                .read_value(SegmentRegister::Cs);
+
+            store.push_word(cs);
+            store
+                .segment_register_store_mut()
+                .write_word_to_segment_register(SegmentRegister::Cs, segment);
+        }
+
+        store.push_word(return_address);
+
+        set_instruction_pointer(reader, target_offset);
+
+        self.num_cycles() + if clock_penalty { 4 } else { 0 }
+    }
+}
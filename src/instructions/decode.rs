@@ -1,114 +1,754 @@
 use super::mov::*;
+use crate::cpu::CpuModel;
+use crate::cursor::ByteCursor;
 use crate::cycle::EstimatedCycleCount;
-use crate::instructions::add::AddInstruction;
-use crate::instructions::arithmetic::ArithmeticInstruction;
-use crate::instructions::compare::CompareInstruction;
+use crate::error::{DecodeError, DecodeErrorReason, DecodeResult};
+use crate::instructions::adjust::AdjustInstructions;
+use crate::instructions::alu::AluInstruction;
+use crate::instructions::bound::BoundInstruction;
+use crate::instructions::call::CallInstructions;
+use crate::instructions::decrement::DecrementInstruction;
+use crate::instructions::divide::DivideInstructions;
+use crate::instructions::encoding::Encoding;
+use crate::instructions::generic::{Instruction, Op};
+use crate::instructions::increment::IncrementInstruction;
+use crate::instructions::interrupt::InterruptInstructions;
+use crate::instructions::io::InputOutputInstructions;
 use crate::instructions::jump::JumpInstructions;
-use crate::instructions::subtract::SubtractInstruction;
-use crate::instructions::Instruction;
+use crate::instructions::multiply::MultiplyInstructions;
+use crate::instructions::negate::NegateInstruction;
+use crate::instructions::not::NotInstruction;
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::prefix::Prefixes;
+use crate::instructions::processor_control::ProcessorControlInstructions;
+use crate::instructions::ret::ReturnInstructions;
+use crate::instructions::shift::ShiftInstructions;
+use crate::instructions::stack::StackInstructions;
+use crate::instructions::string::StringInstructions;
+use crate::instructions::table::{Field, OperandSlot, Table, Trailing};
+use crate::instructions::transfer::TransferInstructions;
+use crate::instructions::{AnyInstruction, Execute};
+use crate::memory::EffectiveAddress;
+use crate::mode::InstructionMode;
 use crate::prelude::*;
+use crate::register::Register;
+use crate::segment_register::SegmentRegister;
 use crate::store::Store;
-use byteorder::ReadBytesExt;
+use byteorder::{LittleEndian, ReadBytesExt};
 use std::fmt::{Display, Formatter};
-use std::fs::File;
-use std::io::BufReader;
 
 pub enum Instructions {
     Mov(MovInstruction),
-    Add(AddInstruction),
-    Sub(SubtractInstruction),
-    Cmp(CompareInstruction),
+    Alu(AluInstruction),
+    Inc(IncrementInstruction),
+    Dec(DecrementInstruction),
+    Neg(NegateInstruction),
+    Not(NotInstruction),
+    Mul(MultiplyInstructions),
+    Div(DivideInstructions),
+    Adjust(AdjustInstructions),
+    Bound(BoundInstruction),
+    Shift(ShiftInstructions),
+    String(StringInstructions),
+    Stack(StackInstructions),
+    Transfer(TransferInstructions),
+    InputOutput(InputOutputInstructions),
     Jump(JumpInstructions),
+    Call(CallInstructions),
+    Return(ReturnInstructions),
+    Interrupt(InterruptInstructions),
+    ProcessorControl(ProcessorControlInstructions),
 }
 
 impl EstimatedCycleCount for Instructions {
     fn num_cycles(&self) -> u32 {
         match self {
             Instructions::Mov(instruction) => instruction.num_cycles(),
-            Instructions::Add(instruction) => instruction.num_cycles(),
-            Instructions::Sub(instruction) => instruction.num_cycles(),
-            Instructions::Cmp(instruction) => instruction.num_cycles(),
+            Instructions::Alu(instruction) => instruction.num_cycles(),
             Instructions::Jump(instruction) => instruction.num_cycles(),
+            Instructions::Not(instruction) => instruction.num_cycles(),
+            Instructions::Shift(instruction) => instruction.num_cycles(),
+            Instructions::String(instruction) => instruction.num_cycles(),
+            Instructions::Stack(instruction) => instruction.num_cycles(),
+            Instructions::Call(instruction) => instruction.num_cycles(),
+            Instructions::Return(instruction) => instruction.num_cycles(),
+            Instructions::Interrupt(instruction) => instruction.num_cycles(),
+            Instructions::Neg(instruction) => instruction.num_cycles(),
+            Instructions::Mul(instruction) => instruction.num_cycles(),
+            Instructions::Div(instruction) => instruction.num_cycles(),
+            Instructions::Adjust(instruction) => instruction.num_cycles(),
+            Instructions::Bound(instruction) => instruction.num_cycles(),
+            Instructions::ProcessorControl(instruction) => instruction.num_cycles(),
+            Instructions::Inc(instruction) => instruction.num_cycles(),
+            Instructions::Dec(instruction) => instruction.num_cycles(),
+            Instructions::Transfer(instruction) => instruction.num_cycles(),
+            Instructions::InputOutput(instruction) => instruction.num_cycles(),
         }
     }
-}
 
-impl Instruction for Instructions {
-    fn execute(&self, reader: &mut BufReader<File>, store: &mut Store) -> u32 {
+    fn cycle_range(&self) -> (u32, u32) {
         match self {
+            Instructions::Mul(instruction) => instruction.cycle_range(),
+            Instructions::Div(instruction) => instruction.cycle_range(),
+            _ => {
+                let num_cycles = self.num_cycles();
+
+                (num_cycles, num_cycles)
+            }
+        }
+    }
+}
+
+impl Execute for Instructions {
+    fn execute(&self, reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        store.set_segment_override(self.instruction().prefixes.segment_override);
+
+        let cycles = match self {
             Instructions::Mov(instruction) => instruction.execute(reader, store),
-            Instructions::Add(instruction) => instruction.execute(reader, store),
-            Instructions::Sub(instruction) => instruction.execute(reader, store),
-            Instructions::Cmp(instruction) => instruction.execute(reader, store),
+            Instructions::Alu(instruction) => instruction.execute(reader, store),
             Instructions::Jump(instruction) => instruction.execute(reader, store),
-        }
+            Instructions::Not(instruction) => instruction.execute(reader, store),
+            Instructions::Shift(instruction) => instruction.execute(reader, store),
+            Instructions::String(instruction) => instruction.execute(reader, store),
+            Instructions::Stack(instruction) => instruction.execute(reader, store),
+            Instructions::Call(instruction) => instruction.execute(reader, store),
+            Instructions::Return(instruction) => instruction.execute(reader, store),
+            Instructions::Interrupt(instruction) => instruction.execute(reader, store),
+            Instructions::Neg(instruction) => instruction.execute(reader, store),
+            Instructions::Mul(instruction) => instruction.execute(reader, store),
+            Instructions::Div(instruction) => instruction.execute(reader, store),
+            Instructions::Adjust(instruction) => instruction.execute(reader, store),
+            Instructions::Bound(instruction) => instruction.execute(reader, store),
+            Instructions::ProcessorControl(instruction) => instruction.execute(reader, store),
+            Instructions::Inc(instruction) => instruction.execute(reader, store),
+            Instructions::Dec(instruction) => instruction.execute(reader, store),
+            Instructions::Transfer(instruction) => instruction.execute(reader, store),
+            Instructions::InputOutput(instruction) => instruction.execute(reader, store),
+        };
+
+        store.set_segment_override(None);
+
+        cycles
     }
 }
 
 impl Display for Instructions {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Instructions::Mov(instruction) => instruction.fmt(f),
-            Instructions::Add(instruction) => instruction.fmt(f),
-            Instructions::Sub(instruction) => instruction.fmt(f),
-            Instructions::Cmp(instruction) => instruction.fmt(f),
-            Instructions::Jump(instruction) => instruction.fmt(f),
-        }
+        Instruction::from(self).fmt(f)
     }
 }
 
 impl Instructions {
-    pub fn read(reader: &mut BufReader<File>, value: Byte) -> Self {
-        match value {
-            value
-                if bit_match!(value, (1, 0, 0, 0, 1, 0, _, _))
-                    | bit_match!(value, (1, 1, 0, 0, 0, 1, 1, _))
-                    | bit_match!(value, (1, 0, 1, 1, _, _, _, _))
-                    | bit_match!(value, (1, 0, 1, 0, 0, 0, _, _))
-                    | bit_match!(value, (1, 0, 0, 0, 1, 1, 1, 0))
-                    | bit_match!(value, (1, 0, 0, 0, 1, 1, 0, 0)) =>
-            {
-                Instructions::Mov(MovInstruction::read(reader, value))
-            }
-            value
-                if bit_match!(value, (0, 0, 0, 0, 0, 0, _, _))
-                    | bit_match!(value, (0, 0, 0, 0, 0, 1, 0, _)) =>
-            {
-                Instructions::Add(AddInstruction::read(reader, value))
-            }
-            value
-                if bit_match!(value, (0, 0, 1, 0, 1, 0, _, _))
-                    | bit_match!(value, (0, 0, 1, 0, 1, 1, 0, _)) =>
-            {
-                Instructions::Sub(SubtractInstruction::read(reader, value))
-            }
-            value
-                if bit_match!(value, (0, 0, 1, 1, 1, 0, _, _))
-                    | bit_match!(value, (0, 0, 1, 1, 1, 1, 0, _)) =>
-            {
-                Instructions::Cmp(CompareInstruction::read(reader, value))
-            }
-            value if bit_match!(value, (1, 0, 0, 0, 0, 0, _, _)) => {
-                let target_specifier_bytes = reader.read_u8().unwrap();
-                reader.seek_relative(-1).unwrap();
-
-                match target_specifier_bytes {
-                    target if bit_match!(target, (_, _, 0, 0, 0, _, _, _)) => {
-                        Instructions::Add(AddInstruction::read(reader, value))
-                    }
-                    target if bit_match!(target, (_, _, 1, 0, 1, _, _, _)) => {
-                        Instructions::Sub(SubtractInstruction::read(reader, value))
-                    }
-                    target if bit_match!(target, (_, _, 1, 1, 1, _, _, _)) => {
-                        Instructions::Cmp(CompareInstruction::read(reader, value))
-                    }
-                    _ => panic!("Invalid instruction found"),
+    /// Decodes the instruction whose first byte, `value`, was just read. When that fails, the
+    /// error holds every byte read for the instruction, starting with `value`.
+    pub fn read(
+        reader: &mut impl ByteCursor,
+        value: Byte,
+        model: CpuModel,
+    ) -> Result<Self, DecodeError> {
+        let offset = reader.stream_position().map_or(0, |position| position - 1);
+
+        Self::read_prefixed(reader, offset, value, model)
+            .map_err(|reason| DecodeError::read_back(reader, offset, reason))
+    }
+
+    fn read_prefixed(
+        reader: &mut impl ByteCursor,
+        offset: u64,
+        value: Byte,
+        model: CpuModel,
+    ) -> DecodeResult<Self> {
+        let (instruction, mode) = read_encoded(reader, offset, value, model)?;
+
+        Ok(Self::from_decoded(&instruction, mode))
+    }
+
+    /// Sorts a decoded instruction into the family that simulates it.
+    fn from_decoded(decoded: &Instruction, mode: Option<InstructionMode>) -> Self {
+        let [first, second, third] = decoded.operands;
+        // The opcode for the coprocessor is written ahead of the operand it is given.
+        let (destination, source) = if decoded.op == Op::Esc {
+            (second, first)
+        } else {
+            (first, second)
+        };
+
+        let instruction = AnyInstruction {
+            is_wide: decoded.is_wide,
+            mode,
+            source,
+            destination: destination.unwrap_or(Operand::AccumulatorWide),
+            prefixes: decoded.prefixes,
+            immediate: third.and_then(|operand| match operand {
+                Operand::Immediate(value) => Some(value),
+                _ => None,
+            }),
+            encoding: decoded.encoding,
+            ..Default::default()
+        };
+        let is_immediate = matches!(destination, Some(Operand::Immediate(_)));
+
+        match decoded.op {
+            Op::Mov => Instructions::Mov(MovInstruction(instruction)),
+            Op::Add
+            | Op::Adc
+            | Op::Sub
+            | Op::Sbb
+            | Op::Cmp
+            | Op::And
+            | Op::Or
+            | Op::Xor
+            | Op::Test => Instructions::Alu(AluInstruction(decoded.op, instruction)),
+            Op::Inc => Instructions::Inc(IncrementInstruction(instruction)),
+            Op::Dec => Instructions::Dec(DecrementInstruction(instruction)),
+            Op::Neg => Instructions::Neg(NegateInstruction(instruction)),
+            Op::Not => Instructions::Not(NotInstruction(instruction)),
+            Op::Mul => Instructions::Mul(MultiplyInstructions::Multiply(instruction)),
+            Op::Imul if third.is_some() => {
+                Instructions::Mul(MultiplyInstructions::SignedMultiplyImmediate(instruction))
+            }
+            Op::Imul => Instructions::Mul(MultiplyInstructions::SignedMultiply(instruction)),
+            Op::Div => Instructions::Div(DivideInstructions::Divide(instruction)),
+            Op::Idiv => Instructions::Div(DivideInstructions::SignedDivide(instruction)),
+            Op::Aaa => Instructions::Adjust(AdjustInstructions::AsciiAdjustForAdd(instruction)),
+            Op::Daa => Instructions::Adjust(AdjustInstructions::DecimalAdjustForAdd(instruction)),
+            Op::Aas => {
+                Instructions::Adjust(AdjustInstructions::AsciiAdjustForSubtract(instruction))
+            }
+            Op::Das => {
+                Instructions::Adjust(AdjustInstructions::DecimalAdjustForSubtract(instruction))
+            }
+            Op::Aam => {
+                Instructions::Adjust(AdjustInstructions::AsciiAdjustForMultiply(instruction))
+            }
+            Op::Aad => Instructions::Adjust(AdjustInstructions::AsciiAdjustForDivide(instruction)),
+            Op::Bound => Instructions::Bound(BoundInstruction(instruction)),
+            Op::Shl => Instructions::Shift(ShiftInstructions::ShiftLeft(instruction)),
+            Op::Shr => Instructions::Shift(ShiftInstructions::ShiftRight(instruction)),
+            Op::Sar => Instructions::Shift(ShiftInstructions::ShiftArithmeticRight(instruction)),
+            Op::Rol => Instructions::Shift(ShiftInstructions::RotateLeft(instruction)),
+            Op::Ror => Instructions::Shift(ShiftInstructions::RotateRight(instruction)),
+            Op::Rcl => Instructions::Shift(ShiftInstructions::RotateThroughCarryLeft(instruction)),
+            Op::Rcr => Instructions::Shift(ShiftInstructions::RotateThroughCarryRight(instruction)),
+            Op::Movs => Instructions::String(StringInstructions::Move(instruction)),
+            Op::Cmps => Instructions::String(StringInstructions::Compare(instruction)),
+            Op::Scas => Instructions::String(StringInstructions::Scan(instruction)),
+            Op::Lods => Instructions::String(StringInstructions::Load(instruction)),
+            Op::Stos => Instructions::String(StringInstructions::Store(instruction)),
+            Op::Ins => Instructions::String(StringInstructions::Input(instruction)),
+            Op::Outs => Instructions::String(StringInstructions::Output(instruction)),
+            Op::Push => Instructions::Stack(StackInstructions::Push(instruction)),
+            Op::Pop => Instructions::Stack(StackInstructions::Pop(instruction)),
+            Op::Pushf => Instructions::Stack(StackInstructions::PushFlags(instruction)),
+            Op::Popf => Instructions::Stack(StackInstructions::PopFlags(instruction)),
+            Op::Pusha => Instructions::Stack(StackInstructions::PushAll(instruction)),
+            Op::Popa => Instructions::Stack(StackInstructions::PopAll(instruction)),
+            Op::Enter => Instructions::Stack(StackInstructions::Enter(instruction)),
+            Op::Leave => Instructions::Stack(StackInstructions::Leave(instruction)),
+            Op::Xchg => Instructions::Transfer(TransferInstructions::Exchange(instruction)),
+            Op::Xlat => Instructions::Transfer(TransferInstructions::Translate(instruction)),
+            Op::Lea => {
+                Instructions::Transfer(TransferInstructions::LoadEffectiveAddress(instruction))
+            }
+            Op::Lds => {
+                Instructions::Transfer(TransferInstructions::LoadPointerUsingDs(instruction))
+            }
+            Op::Les => {
+                Instructions::Transfer(TransferInstructions::LoadPointerUsingEs(instruction))
+            }
+            Op::Lahf => Instructions::Transfer(TransferInstructions::LoadAhWithFlags(instruction)),
+            Op::Sahf => Instructions::Transfer(TransferInstructions::StoreAhIntoFlags(instruction)),
+            Op::Cbw => Instructions::Transfer(TransferInstructions::ConvertByteToWord(instruction)),
+            Op::Cwd => {
+                Instructions::Transfer(TransferInstructions::ConvertWordToDoubleWord(instruction))
+            }
+            Op::In => Instructions::InputOutput(InputOutputInstructions::Input(instruction)),
+            Op::Out => Instructions::InputOutput(InputOutputInstructions::Output(instruction)),
+            Op::Je => Instructions::Jump(JumpInstructions::JumpOnEqualOrZero(instruction)),
+            Op::Jl => Instructions::Jump(JumpInstructions::JumpOnLess(instruction)),
+            Op::Jle => Instructions::Jump(JumpInstructions::JumpOnLessOrEqual(instruction)),
+            Op::Jb => Instructions::Jump(JumpInstructions::JumpOnBelow(instruction)),
+            Op::Jbe => Instructions::Jump(JumpInstructions::JumpOnBelowOrEqual(instruction)),
+            Op::Jp => Instructions::Jump(JumpInstructions::JumpOnParityEven(instruction)),
+            Op::Jo => Instructions::Jump(JumpInstructions::JumpOnOverflow(instruction)),
+            Op::Js => Instructions::Jump(JumpInstructions::JumpOnSign(instruction)),
+            Op::Jne => Instructions::Jump(JumpInstructions::JumpOnNotEqualAndNotZero(instruction)),
+            Op::Jnl => Instructions::Jump(JumpInstructions::JumpOnNotLess(instruction)),
+            Op::Jnle => Instructions::Jump(JumpInstructions::JumpOnNotLessAndNotEqual(instruction)),
+            Op::Jnb => Instructions::Jump(JumpInstructions::JumpOnNotBelow(instruction)),
+            Op::Jnbe => {
+                Instructions::Jump(JumpInstructions::JumpOnNotBelowAndNotEqual(instruction))
+            }
+            Op::Jnp => Instructions::Jump(JumpInstructions::JumpOnParityOdd(instruction)),
+            Op::Jno => Instructions::Jump(JumpInstructions::JumpOnNotOverflow(instruction)),
+            Op::Jns => Instructions::Jump(JumpInstructions::JumpOnNotSign(instruction)),
+            Op::Loop => Instructions::Jump(JumpInstructions::Loop(instruction)),
+            Op::Loope => Instructions::Jump(JumpInstructions::LoopWhileZeroOrEqual(instruction)),
+            Op::Loopne => {
+                Instructions::Jump(JumpInstructions::LoopWhileNotZeroAndNotEqual(instruction))
+            }
+            Op::Jcxz => Instructions::Jump(JumpInstructions::JumpOnCxZero(instruction)),
+            Op::Jmp => Instructions::Jump(match destination {
+                Some(Operand::Immediate(ImmediateValue::SignedByte(_))) => {
+                    JumpInstructions::JumpDirectWithinSegmentShort(instruction)
+                }
+                _ if is_immediate => JumpInstructions::JumpDirectWithinSegment(instruction),
+                _ => JumpInstructions::JumpIndirectWithinSegment(instruction),
+            }),
+            Op::JmpFar => Instructions::Jump(match destination {
+                Some(Operand::FarPointer(..)) => {
+                    JumpInstructions::JumpDirectIntersegment(instruction)
                 }
+                _ => JumpInstructions::JumpIndirectIntersegment(instruction),
+            }),
+            Op::Call if is_immediate => {
+                Instructions::Call(CallInstructions::DirectWithinSegment(instruction))
+            }
+            Op::Call => Instructions::Call(CallInstructions::IndirectWithinSegment(instruction)),
+            Op::CallFar => Instructions::Call(match destination {
+                Some(Operand::FarPointer(..)) => CallInstructions::DirectIntersegment(instruction),
+                _ => CallInstructions::IndirectIntersegment(instruction),
+            }),
+            Op::Ret if is_immediate => Instructions::Return(
+                ReturnInstructions::WithinSegmentAddingImmediateToSp(instruction),
+            ),
+            Op::Ret => Instructions::Return(ReturnInstructions::WithinSegment(instruction)),
+            Op::Retf if is_immediate => Instructions::Return(
+                ReturnInstructions::IntersegmentAddingImmediateToSp(instruction),
+            ),
+            Op::Retf => Instructions::Return(ReturnInstructions::Intersegment(instruction)),
+            Op::Int => Instructions::Interrupt(InterruptInstructions::TypeSpecified(instruction)),
+            Op::Int3 => Instructions::Interrupt(InterruptInstructions::Type3(instruction)),
+            Op::Into => Instructions::Interrupt(InterruptInstructions::OnOverflow(instruction)),
+            Op::Iret => Instructions::Interrupt(InterruptInstructions::Return(instruction)),
+            Op::Clc => Instructions::ProcessorControl(ProcessorControlInstructions::ClearCarry(
+                instruction,
+            )),
+            Op::Cmc => Instructions::ProcessorControl(
+                ProcessorControlInstructions::ComplementCarry(instruction),
+            ),
+            Op::Stc => {
+                Instructions::ProcessorControl(ProcessorControlInstructions::SetCarry(instruction))
+            }
+            Op::Cld => Instructions::ProcessorControl(
+                ProcessorControlInstructions::ClearDirection(instruction),
+            ),
+            Op::Std => Instructions::ProcessorControl(ProcessorControlInstructions::SetDirection(
+                instruction,
+            )),
+            Op::Cli => Instructions::ProcessorControl(
+                ProcessorControlInstructions::ClearInterrupt(instruction),
+            ),
+            Op::Sti => Instructions::ProcessorControl(ProcessorControlInstructions::SetInterrupt(
+                instruction,
+            )),
+            Op::Hlt => {
+                Instructions::ProcessorControl(ProcessorControlInstructions::Halt(instruction))
+            }
+            Op::Wait => {
+                Instructions::ProcessorControl(ProcessorControlInstructions::Wait(instruction))
+            }
+            Op::Esc => {
+                Instructions::ProcessorControl(ProcessorControlInstructions::Escape(instruction))
+            }
+            Op::Nop => Instructions::ProcessorControl(ProcessorControlInstructions::NoOperation(
+                instruction,
+            )),
+        }
+    }
+
+    /// The operation, as the generic [`Instruction`] names it.
+    pub fn op(&self) -> Op {
+        match self {
+            Instructions::Mov(_) => Op::Mov,
+            Instructions::Alu(AluInstruction(op, _)) => *op,
+            Instructions::Inc(_) => Op::Inc,
+            Instructions::Dec(_) => Op::Dec,
+            Instructions::Neg(_) => Op::Neg,
+            Instructions::Not(_) => Op::Not,
+            Instructions::Mul(instruction) => match instruction {
+                MultiplyInstructions::Multiply(_) => Op::Mul,
+                MultiplyInstructions::SignedMultiply(_)
+                | MultiplyInstructions::SignedMultiplyImmediate(_) => Op::Imul,
+            },
+            Instructions::Div(instruction) => match instruction {
+                DivideInstructions::Divide(_) => Op::Div,
+                DivideInstructions::SignedDivide(_) => Op::Idiv,
+            },
+            Instructions::Adjust(instruction) => match instruction {
+                AdjustInstructions::AsciiAdjustForAdd(_) => Op::Aaa,
+                AdjustInstructions::DecimalAdjustForAdd(_) => Op::Daa,
+                AdjustInstructions::AsciiAdjustForSubtract(_) => Op::Aas,
+                AdjustInstructions::DecimalAdjustForSubtract(_) => Op::Das,
+                AdjustInstructions::AsciiAdjustForMultiply(_) => Op::Aam,
+                AdjustInstructions::AsciiAdjustForDivide(_) => Op::Aad,
+            },
+            Instructions::Bound(_) => Op::Bound,
+            Instructions::Shift(instruction) => match instruction {
+                ShiftInstructions::ShiftLeft(_) => Op::Shl,
+                ShiftInstructions::ShiftRight(_) => Op::Shr,
+                ShiftInstructions::ShiftArithmeticRight(_) => Op::Sar,
+                ShiftInstructions::RotateLeft(_) => Op::Rol,
+                ShiftInstructions::RotateRight(_) => Op::Ror,
+                ShiftInstructions::RotateThroughCarryLeft(_) => Op::Rcl,
+                ShiftInstructions::RotateThroughCarryRight(_) => Op::Rcr,
+            },
+            Instructions::String(instruction) => match instruction {
+                StringInstructions::Move(_) => Op::Movs,
+                StringInstructions::Compare(_) => Op::Cmps,
+                StringInstructions::Scan(_) => Op::Scas,
+                StringInstructions::Load(_) => Op::Lods,
+                StringInstructions::Store(_) => Op::Stos,
+                StringInstructions::Input(_) => Op::Ins,
+                StringInstructions::Output(_) => Op::Outs,
+            },
+            Instructions::Stack(instruction) => match instruction {
+                StackInstructions::Push(_) => Op::Push,
+                StackInstructions::Pop(_) => Op::Pop,
+                StackInstructions::PushFlags(_) => Op::Pushf,
+                StackInstructions::PopFlags(_) => Op::Popf,
+                StackInstructions::PushAll(_) => Op::Pusha,
+                StackInstructions::PopAll(_) => Op::Popa,
+                StackInstructions::Enter(_) => Op::Enter,
+                StackInstructions::Leave(_) => Op::Leave,
+            },
+            Instructions::Transfer(instruction) => match instruction {
+                TransferInstructions::Exchange(_) => Op::Xchg,
+                TransferInstructions::Translate(_) => Op::Xlat,
+                TransferInstructions::LoadEffectiveAddress(_) => Op::Lea,
+                TransferInstructions::LoadPointerUsingDs(_) => Op::Lds,
+                TransferInstructions::LoadPointerUsingEs(_) => Op::Les,
+                TransferInstructions::LoadAhWithFlags(_) => Op::Lahf,
+                TransferInstructions::StoreAhIntoFlags(_) => Op::Sahf,
+                TransferInstructions::ConvertByteToWord(_) => Op::Cbw,
+                TransferInstructions::ConvertWordToDoubleWord(_) => Op::Cwd,
+            },
+            Instructions::InputOutput(instruction) => match instruction {
+                InputOutputInstructions::Input(_) => Op::In,
+                InputOutputInstructions::Output(_) => Op::Out,
+            },
+            Instructions::Jump(instruction) => match instruction {
+                JumpInstructions::JumpOnEqualOrZero(_) => Op::Je,
+                JumpInstructions::JumpOnLess(_) => Op::Jl,
+                JumpInstructions::JumpOnLessOrEqual(_) => Op::Jle,
+                JumpInstructions::JumpOnBelow(_) => Op::Jb,
+                JumpInstructions::JumpOnBelowOrEqual(_) => Op::Jbe,
+                JumpInstructions::JumpOnParityEven(_) => Op::Jp,
+                JumpInstructions::JumpOnOverflow(_) => Op::Jo,
+                JumpInstructions::JumpOnSign(_) => Op::Js,
+                JumpInstructions::JumpOnNotEqualAndNotZero(_) => Op::Jne,
+                JumpInstructions::JumpOnNotLess(_) => Op::Jnl,
+                JumpInstructions::JumpOnNotLessAndNotEqual(_) => Op::Jnle,
+                JumpInstructions::JumpOnNotBelow(_) => Op::Jnb,
+                JumpInstructions::JumpOnNotBelowAndNotEqual(_) => Op::Jnbe,
+                JumpInstructions::JumpOnParityOdd(_) => Op::Jnp,
+                JumpInstructions::JumpOnNotOverflow(_) => Op::Jno,
+                JumpInstructions::JumpOnNotSign(_) => Op::Jns,
+                JumpInstructions::Loop(_) => Op::Loop,
+                JumpInstructions::LoopWhileZeroOrEqual(_) => Op::Loope,
+                JumpInstructions::LoopWhileNotZeroAndNotEqual(_) => Op::Loopne,
+                JumpInstructions::JumpOnCxZero(_) => Op::Jcxz,
+                JumpInstructions::JumpDirectWithinSegment(_)
+                | JumpInstructions::JumpDirectWithinSegmentShort(_)
+                | JumpInstructions::JumpIndirectWithinSegment(_) => Op::Jmp,
+                JumpInstructions::JumpDirectIntersegment(_)
+                | JumpInstructions::JumpIndirectIntersegment(_) => Op::JmpFar,
+            },
+            Instructions::Call(instruction) => match instruction {
+                CallInstructions::DirectWithinSegment(_)
+                | CallInstructions::IndirectWithinSegment(_) => Op::Call,
+                CallInstructions::DirectIntersegment(_)
+                | CallInstructions::IndirectIntersegment(_) => Op::CallFar,
+            },
+            Instructions::Return(instruction) => match instruction {
+                ReturnInstructions::WithinSegment(_)
+                | ReturnInstructions::WithinSegmentAddingImmediateToSp(_) => Op::Ret,
+                ReturnInstructions::Intersegment(_)
+                | ReturnInstructions::IntersegmentAddingImmediateToSp(_) => Op::Retf,
+            },
+            Instructions::Interrupt(instruction) => match instruction {
+                InterruptInstructions::TypeSpecified(_) => Op::Int,
+                InterruptInstructions::Type3(_) => Op::Int3,
+                InterruptInstructions::OnOverflow(_) => Op::Into,
+                InterruptInstructions::Return(_) => Op::Iret,
+            },
+            Instructions::ProcessorControl(instruction) => match instruction {
+                ProcessorControlInstructions::ClearCarry(_) => Op::Clc,
+                ProcessorControlInstructions::ComplementCarry(_) => Op::Cmc,
+                ProcessorControlInstructions::SetCarry(_) => Op::Stc,
+                ProcessorControlInstructions::ClearDirection(_) => Op::Cld,
+                ProcessorControlInstructions::SetDirection(_) => Op::Std,
+                ProcessorControlInstructions::ClearInterrupt(_) => Op::Cli,
+                ProcessorControlInstructions::SetInterrupt(_) => Op::Sti,
+                ProcessorControlInstructions::Halt(_) => Op::Hlt,
+                ProcessorControlInstructions::Wait(_) => Op::Wait,
+                ProcessorControlInstructions::Escape(_) => Op::Esc,
+                ProcessorControlInstructions::NoOperation(_) => Op::Nop,
+            },
+        }
+    }
+
+    /// Whether the operation implies all of its operands, leaving the destination of the
+    /// underlying instruction at its default.
+    pub(crate) fn has_implied_operands(&self) -> bool {
+        match self {
+            Instructions::Adjust(instruction) => !matches!(
+                instruction,
+                AdjustInstructions::AsciiAdjustForMultiply(_)
+                    | AdjustInstructions::AsciiAdjustForDivide(_)
+            ),
+            Instructions::String(_) => true,
+            Instructions::Stack(instruction) => !matches!(
+                instruction,
+                StackInstructions::Push(_)
+                    | StackInstructions::Pop(_)
+                    | StackInstructions::Enter(_)
+            ),
+            Instructions::Transfer(instruction) => !matches!(
+                instruction,
+                TransferInstructions::Exchange(_)
+                    | TransferInstructions::LoadEffectiveAddress(_)
+                    | TransferInstructions::LoadPointerUsingDs(_)
+                    | TransferInstructions::LoadPointerUsingEs(_)
+            ),
+            Instructions::Return(instruction) => matches!(
+                instruction,
+                ReturnInstructions::WithinSegment(_) | ReturnInstructions::Intersegment(_)
+            ),
+            Instructions::Interrupt(instruction) => {
+                !matches!(instruction, InterruptInstructions::TypeSpecified(_))
+            }
+            Instructions::ProcessorControl(instruction) => {
+                !matches!(instruction, ProcessorControlInstructions::Escape(_))
+            }
+            _ => false,
+        }
+    }
+
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            Instructions::Mov(MovInstruction(instruction))
+            | Instructions::Alu(AluInstruction(_, instruction))
+            | Instructions::Inc(IncrementInstruction(instruction))
+            | Instructions::Dec(DecrementInstruction(instruction))
+            | Instructions::Neg(NegateInstruction(instruction))
+            | Instructions::Not(NotInstruction(instruction))
+            | Instructions::Bound(BoundInstruction(instruction)) => instruction,
+            Instructions::Mul(instruction) => instruction.instruction(),
+            Instructions::Div(instruction) => instruction.instruction(),
+            Instructions::Adjust(instruction) => instruction.instruction(),
+            Instructions::Shift(instruction) => instruction.instruction(),
+            Instructions::String(instruction) => instruction.instruction(),
+            Instructions::Stack(instruction) => instruction.instruction(),
+            Instructions::Transfer(instruction) => instruction.instruction(),
+            Instructions::InputOutput(instruction) => instruction.instruction(),
+            Instructions::Jump(instruction) => instruction.instruction(),
+            Instructions::Call(instruction) => instruction.instruction(),
+            Instructions::Return(instruction) => instruction.instruction(),
+            Instructions::Interrupt(instruction) => instruction.instruction(),
+            Instructions::ProcessorControl(instruction) => instruction.instruction(),
+        }
+    }
+
+    /// Where the instruction was decoded from, or nothing for one built by hand.
+    pub fn encoding(&self) -> &Encoding {
+        &self.instruction().encoding
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            Instructions::Mov(MovInstruction(instruction))
+            | Instructions::Alu(AluInstruction(_, instruction))
+            | Instructions::Inc(IncrementInstruction(instruction))
+            | Instructions::Dec(DecrementInstruction(instruction))
+            | Instructions::Neg(NegateInstruction(instruction))
+            | Instructions::Not(NotInstruction(instruction))
+            | Instructions::Bound(BoundInstruction(instruction)) => instruction,
+            Instructions::Mul(instruction) => instruction.instruction_mut(),
+            Instructions::Div(instruction) => instruction.instruction_mut(),
+            Instructions::Adjust(instruction) => instruction.instruction_mut(),
+            Instructions::Shift(instruction) => instruction.instruction_mut(),
+            Instructions::String(instruction) => instruction.instruction_mut(),
+            Instructions::Stack(instruction) => instruction.instruction_mut(),
+            Instructions::Transfer(instruction) => instruction.instruction_mut(),
+            Instructions::InputOutput(instruction) => instruction.instruction_mut(),
+            Instructions::Jump(instruction) => instruction.instruction_mut(),
+            Instructions::Call(instruction) => instruction.instruction_mut(),
+            Instructions::Return(instruction) => instruction.instruction_mut(),
+            Instructions::Interrupt(instruction) => instruction.instruction_mut(),
+            Instructions::ProcessorControl(instruction) => instruction.instruction_mut(),
+        }
+    }
+}
+
+/// Decodes the instruction whose first byte, `value`, was just read, by looking its opcode up
+/// in the encoding table. Also gives the mode of its ModRM byte, when it has one.
+pub(crate) fn read_encoded(
+    reader: &mut impl ByteCursor,
+    offset: u64,
+    value: Byte,
+    model: CpuModel,
+) -> DecodeResult<(Instruction, Option<InstructionMode>)> {
+    let mut prefixes = Prefixes::default();
+    let mut value = value;
+
+    while Prefixes::is_prefix(value, model) {
+        prefixes.add(value);
+        value = reader.read_u8()?;
+    }
+
+    let opcode = match value {
+        // The 8086 doesn't decode every opcode bit, so what later models repurposed as new
+        // instructions runs as a copy of the conditional jumps and returns.
+        value if model == CpuModel::Intel8086 && bit_match!(value, (0, 1, 1, 0, _, _, _, _)) => {
+            value | 0b0001_0000
+        }
+        value if model == CpuModel::Intel8086 && bit_match!(value, (1, 1, 0, 0, _, 0, 0, _)) => {
+            value | 0b0000_0010
+        }
+        // The 80186 reserves pop cs, along with the rest of the opcodes it doesn't define.
+        value if model >= CpuModel::Intel80186 && bit_match!(value, (0, 0, 0, 0, 1, 1, 1, 1)) => {
+            return Err(DecodeErrorReason::UnknownOpcode);
+        }
+        value => value,
+    };
+
+    let table = Table::get();
+    let mut candidates = table.candidates(opcode, model).peekable();
+    let (_, first) = candidates.peek().ok_or(DecodeErrorReason::UnknownOpcode)?;
+
+    // Encodings sharing an opcode byte either all have a ModRM byte or none of them do.
+    let bytes = if first.has_modrm() {
+        [opcode, reader.read_u8()?]
+    } else {
+        [opcode, 0]
+    };
+
+    let (spec, layout) = candidates
+        .find(|(_, layout)| {
+            layout
+                .fixed
+                .iter()
+                .zip(bytes)
+                .all(|(&(mask, value), byte)| byte & mask == value)
+        })
+        .ok_or(DecodeErrorReason::InvalidModRm)?;
+
+    let field = |field| layout.field(field).map(|slot| slot.extract(&bytes));
+    let is_wide = layout.is_wide || field(Field::Wide) == Some(1);
+    let is_signed = field(Field::SignExtend) == Some(1);
+
+    let mode = layout.has_modrm().then(|| InstructionMode::from(bytes[1]));
+    let register_or_memory = match mode {
+        Some(mode) => Some(Operand::read(reader, mode, bytes[1], is_wide, prefixes)?),
+        None => None,
+    };
+
+    let mut trailing = Vec::with_capacity(layout.trailing.len());
+
+    for &field in &layout.trailing {
+        trailing.push(read_trailing(reader, field, is_wide, is_signed, prefixes)?);
+    }
+
+    let mut trailing = trailing.into_iter();
+    let mut operands = [None; 3];
+
+    for (slot, operand) in layout.operands.iter().zip(&mut operands) {
+        *operand = Some(match slot {
+            OperandSlot::Register => {
+                let register = field(Field::Register).expect("reg operand without a reg field");
+
+                Operand::Register(
+                    Register::from(register << 1 | u8::from(is_wide)).sized(is_wide, prefixes),
+                )
             }
-            value if JumpInstructions::is_jump_instruction(value) => {
-                Instructions::Jump(JumpInstructions::try_from((value, reader)).unwrap())
+            OperandSlot::RegisterOrMemory => {
+                register_or_memory.expect("r/m operand without a ModRM byte")
             }
-            _ => panic!("Unsupported instruction: {value}"),
+            OperandSlot::Memory => match register_or_memory {
+                Some(memory @ Operand::Memory(_)) => memory,
+                _ => return Err(DecodeErrorReason::InvalidModRm),
+            },
+            OperandSlot::SegmentRegister => Operand::SegmentRegister(SegmentRegister::from(
+                field(Field::SegmentRegister).expect("sr operand without an sr field"),
+            )),
+            OperandSlot::Accumulator => Operand::accumulator(is_wide, prefixes),
+            OperandSlot::Dx => Operand::Register(Register::Dx),
+            OperandSlot::Cl => Operand::Register(Register::Cl),
+            OperandSlot::Count if field(Field::Variable) == Some(1) => {
+                Operand::Register(Register::Cl)
+            }
+            OperandSlot::Count => Operand::Immediate(ImmediateValue::SignedByte(1)),
+            OperandSlot::Escape => {
+                let escape = field(Field::Escape).expect("esc operand without an esc field");
+                let register = field(Field::Register).expect("esc operand without a reg field");
+
+                Operand::Immediate(ImmediateValue::SignedWord(i16::from(
+                    escape << 3 | register,
+                )))
+            }
+            OperandSlot::Trailing => trailing
+                .next()
+                .expect("Fewer trailing fields than operands"),
+        });
+    }
+
+    if field(Field::Direction) == Some(1) {
+        operands.swap(0, 1);
+    }
+
+    let instruction = Instruction {
+        op: spec.op,
+        prefixes,
+        operands,
+        is_wide,
+        encoding: Encoding::read_back(reader, offset)?,
+    };
+
+    Ok((instruction, mode))
+}
+
+fn read_trailing(
+    reader: &mut impl ByteCursor,
+    trailing: Trailing,
+    is_wide: Wide,
+    is_signed: bool,
+    prefixes: Prefixes,
+) -> DecodeResult<Operand> {
+    Ok(match trailing {
+        Trailing::Data if !is_wide => {
+            Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?))
+        }
+        Trailing::Data if is_signed => {
+            Operand::Immediate(ImmediateValue::extend_byte(reader.read_i8()?, prefixes))
+        }
+        Trailing::Data | Trailing::IpIncrement => {
+            Operand::Immediate(ImmediateValue::read_wide(reader, prefixes)?)
         }
+        Trailing::Data8 | Trailing::IpIncrement8 => {
+            Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?))
+        }
+        Trailing::UnsignedData8 => {
+            Operand::Immediate(ImmediateValue::SignedWord(i16::from(reader.read_u8()?)))
+        }
+        Trailing::Data16 => Operand::Immediate(ImmediateValue::SignedWord(
+            reader.read_i16::<LittleEndian>()?,
+        )),
+        Trailing::Address => Operand::Memory(EffectiveAddress::read_direct(reader, prefixes)?),
+        Trailing::FarPointer => {
+            let offset = read_far_offset(reader, prefixes)?;
+            let segment = reader.read_u16::<LittleEndian>()?;
+
+            Operand::FarPointer(segment, offset)
+        }
+    })
+}
+
+/// Reads the offset of a direct far pointer, which an operand size prefix widens to 32 bits.
+fn read_far_offset(reader: &mut impl ByteCursor, prefixes: Prefixes) -> std::io::Result<u32> {
+    if prefixes.operand_size_override {
+        reader.read_u32::<LittleEndian>()
+    } else {
+        reader.read_u16::<LittleEndian>().map(u32::from)
     }
 }
@@ -0,0 +1,42 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::flag_register::FlagRegister;
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::{AnyInstruction, Execute};
+use crate::store::Store;
+
+pub struct DecrementInstruction(pub AnyInstruction);
+
+impl EstimatedCycleCount for DecrementInstruction {
+    fn num_cycles(&self) -> u32 {
+        match (self.0.destination, self.0.mode) {
+            // The single byte form only exists for word registers.
+            (Operand::Register(_), None) => 2,
+            (Operand::Register(_), Some(_)) => 3,
+            (Operand::Memory(ea), _) => 15 + ea.num_cycles(),
+            _ => panic!("Invalid DEC operation"),
+        }
+    }
+}
+
+impl Execute for DecrementInstruction {
+    fn execute(&self, _reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        let (value, clock_penalty) = self.0.destination.to_immediate_value(self.0.is_wide, store);
+
+        let one = if self.0.is_wide {
+            ImmediateValue::SignedWord(1)
+        } else {
+            ImmediateValue::SignedByte(1)
+        };
+
+        let op_result = (value - one).preserving(FlagRegister::Carry);
+
+        store.flag_register_store_mut().set_flags_on_op(op_result);
+
+        self.0
+            .destination
+            .write_value(op_result.value(), self.0.is_wide, store);
+
+        self.num_cycles() + if clock_penalty { 4 } else { 0 }
+    }
+}
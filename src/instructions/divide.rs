@@ -0,0 +1,139 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::{interpolate_cycles, EstimatedCycleCount};
+use crate::instructions::interrupt::raise_interrupt;
+use crate::instructions::operands::Operand;
+use crate::instructions::{AnyInstruction, Execute};
+use crate::register::Register;
+use crate::store::Store;
+
+pub enum DivideInstructions {
+    Divide(AnyInstruction),
+    SignedDivide(AnyInstruction),
+}
+
+impl DivideInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            DivideInstructions::Divide(instruction)
+            | DivideInstructions::SignedDivide(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            DivideInstructions::Divide(instruction)
+            | DivideInstructions::SignedDivide(instruction) => instruction,
+        }
+    }
+}
+
+impl EstimatedCycleCount for DivideInstructions {
+    /// Without the operands the best guess is halfway through the documented range.
+    fn num_cycles(&self) -> u32 {
+        let (min, max) = self.cycle_range();
+
+        (min + max) / 2
+    }
+
+    fn cycle_range(&self) -> (u32, u32) {
+        let instruction = self.instruction();
+
+        let (min, max) = match (self, instruction.is_wide) {
+            (DivideInstructions::Divide(_), false) => (80, 90),
+            (DivideInstructions::Divide(_), true) => (144, 162),
+            (DivideInstructions::SignedDivide(_), false) => (101, 112),
+            (DivideInstructions::SignedDivide(_), true) => (165, 184),
+        };
+
+        match instruction.destination {
+            Operand::Register(_) => (min, max),
+            Operand::Memory(ea) => (min + 6 + ea.num_cycles(), max + 6 + ea.num_cycles()),
+            _ => panic!("Invalid divide operation"),
+        }
+    }
+}
+
+impl DivideInstructions {
+    /// Divides the accumulator by `divisor`, returning the quotient and remainder, or `None` when
+    /// the quotient doesn't fit its destination.
+    fn divide(&self, is_wide: bool, divisor: i16, store: &Store) -> Option<(i32, i32)> {
+        let registers = store.register_store();
+
+        let dividend = registers.read_word_from_register(Register::Ax);
+        let high_dividend = registers.read_word_from_register(Register::Dx);
+
+        match (self, is_wide) {
+            (DivideInstructions::Divide(_), false) => {
+                let dividend = i32::from(dividend);
+                let divisor = i32::from(divisor as u8);
+
+                (divisor != 0 && dividend / divisor <= 0xFF)
+                    .then(|| (dividend / divisor, dividend % divisor))
+            }
+            (DivideInstructions::Divide(_), true) => {
+                let dividend = (u64::from(high_dividend) << 16) | u64::from(dividend);
+                let divisor = u64::from(divisor as u16);
+
+                (divisor != 0 && dividend / divisor <= 0xFFFF)
+                    .then(|| ((dividend / divisor) as i32, (dividend % divisor) as i32))
+            }
+            // The 8086 faults on the most negative quotient as well, unlike its successors.
+            (DivideInstructions::SignedDivide(_), false) => {
+                let dividend = i32::from(dividend as i16);
+                let divisor = i32::from(divisor as i8);
+
+                (divisor != 0 && (dividend / divisor).abs() <= i32::from(i8::MAX))
+                    .then(|| (dividend / divisor, dividend % divisor))
+            }
+            (DivideInstructions::SignedDivide(_), true) => {
+                // Widened past 32 bits so that dividing the most negative dividend by -1 can't
+                // overflow.
+                let dividend =
+                    i64::from(((u32::from(high_dividend) << 16) | u32::from(dividend)) as i32);
+                let divisor = i64::from(divisor);
+
+                (divisor != 0 && (dividend / divisor).abs() <= i64::from(i16::MAX))
+                    .then(|| ((dividend / divisor) as i32, (dividend % divisor) as i32))
+            }
+        }
+    }
+}
+
+impl Execute for DivideInstructions {
+    /// Every flag is left undefined, so they are left as they were. The quotient's set bits place
+    /// the instruction within its range, as each of them costs the microcode a subtraction.
+    fn execute(&self, reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        let instruction = self.instruction();
+        let is_wide = instruction.is_wide;
+
+        let (divisor, clock_penalty) = instruction.destination.to_immediate_value(is_wide, store);
+        let clock_penalty = if clock_penalty { 4 } else { 0 };
+
+        let Some((quotient, remainder)) = self.divide(is_wide, divisor.into(), store) else {
+            // A divide error gives up early, so only the fastest timing is charged on top of
+            // entering the interrupt.
+            raise_interrupt(reader, store, 0);
+
+            return self.cycle_range().0 + 51 + clock_penalty;
+        };
+
+        let registers = store.register_store_mut();
+
+        if is_wide {
+            registers.write_word_to_register(Register::Ax, quotient as u16);
+            registers.write_word_to_register(Register::Dx, remainder as u16);
+        } else {
+            registers.write_byte_to_register(Register::Al, quotient as u8);
+            registers.write_byte_to_register(Register::Ah, remainder as u8);
+        }
+
+        let num_bits = if is_wide { 16 } else { 8 };
+        let quotient_bits = if is_wide {
+            (quotient as u16).count_ones()
+        } else {
+            (quotient as u8).count_ones()
+        };
+
+        interpolate_cycles(self.cycle_range(), quotient_bits, num_bits) + clock_penalty
+    }
+}
@@ -0,0 +1,454 @@
+use crate::cpu::CpuModel;
+use crate::error::EncodeError;
+use crate::instructions::decode::Instructions;
+use crate::instructions::generic::Instruction;
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::prefix::Prefixes;
+use crate::instructions::table::{Field, Layout, OperandSlot, Table, Trailing, ENCODINGS};
+use crate::memory::{Displacement, EffectiveAddress, ExtendedAddress};
+use crate::prelude::*;
+use crate::register::Register;
+use std::ops::RangeInclusive;
+
+/// Which encoding to pick for an instruction that has several, as the d bit, sign extended
+/// immediates, the accumulator forms and the width of displacements all allow.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum EncodingChoice {
+    /// The fewest bytes, taking the earliest encoding in the table among equals.
+    #[default]
+    Shortest,
+    /// The opcode and ModRM mode the instruction was decoded with, as long as they can still
+    /// encode it, and the shortest encoding otherwise.
+    Original,
+}
+
+/// One encoding of an instruction.
+struct Candidate {
+    bytes: Vec<Byte>,
+    /// Where the opcode starts, after the prefixes.
+    opcode: usize,
+    has_modrm: bool,
+    /// How many bytes its first immediate or branch displacement takes, if it has one.
+    immediate_size: Option<usize>,
+}
+
+impl Candidate {
+    /// Whether the candidate has the same opcode as `original`, and the same ModRM mode when
+    /// it has a ModRM byte.
+    fn keeps_form_of(&self, original: &[Byte]) -> bool {
+        let mode = |bytes: &[Byte]| bytes.get(1).map(|modrm| modrm >> 6);
+        let bytes = &self.bytes[self.opcode..];
+
+        bytes[0] == original[0] && (!self.has_modrm || mode(bytes) == mode(original))
+    }
+}
+
+/// What one operand sets in an encoding.
+#[derive(Clone, Default)]
+struct Assignment {
+    fields: Vec<(Field, Byte)>,
+    /// The SIB byte and displacement of a memory operand.
+    address: Vec<Byte>,
+    trailing: Vec<Operand>,
+}
+
+impl Assignment {
+    fn fields(fields: &[(Field, Byte)]) -> Self {
+        Assignment {
+            fields: fields.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds `other` on top, unless the two disagree on a field.
+    fn merge(&self, other: &Assignment) -> Option<Assignment> {
+        let mut merged = self.clone();
+
+        for &(field, value) in &other.fields {
+            match merged.field(field) {
+                Some(existing) if existing != value => return None,
+                Some(_) => {}
+                None => merged.fields.push((field, value)),
+            }
+        }
+
+        merged.address.extend(&other.address);
+        merged.trailing.extend(&other.trailing);
+
+        Some(merged)
+    }
+
+    fn field(&self, field: Field) -> Option<Byte> {
+        self.fields
+            .iter()
+            .find(|(candidate, _)| *candidate == field)
+            .map(|&(_, value)| value)
+    }
+}
+
+impl Instruction {
+    /// Encodes the instruction into machine code, picking between its encodings by `choice`.
+    pub fn encode(&self, choice: EncodingChoice) -> Result<Vec<Byte>, EncodeError> {
+        let candidates = self.candidates(CpuModel::Intel80386);
+
+        let original = match choice {
+            EncodingChoice::Shortest => None,
+            EncodingChoice::Original => self.original_form(),
+        };
+        let kept = original.and_then(|form| {
+            candidates
+                .iter()
+                .filter(|candidate| candidate.keeps_form_of(form))
+                .min_by_key(|candidate| candidate.bytes.len())
+        });
+
+        kept.or_else(|| {
+            candidates
+                .iter()
+                .min_by_key(|candidate| candidate.bytes.len())
+        })
+        .map(|candidate| candidate.bytes.clone())
+        .ok_or_else(|| self.encode_error())
+    }
+
+    /// Encodes the instruction as the assembler reads it: the shortest encoding `model` knows,
+    /// with an immediate of `immediate_size` bytes when the size is written out, like in
+    /// `push word 1` or `jmp near $+3`.
+    pub(crate) fn encode_for(
+        &self,
+        model: CpuModel,
+        immediate_size: Option<usize>,
+    ) -> Result<Vec<Byte>, EncodeError> {
+        self.candidates(model)
+            .into_iter()
+            .filter(|candidate| {
+                immediate_size.is_none() || candidate.immediate_size == immediate_size
+            })
+            .min_by_key(|candidate| candidate.bytes.len())
+            .map(|candidate| candidate.bytes)
+            .ok_or_else(|| self.encode_error())
+    }
+
+    /// Every encoding of the instruction, in table order.
+    pub fn encodings(&self) -> Vec<Vec<Byte>> {
+        self.candidates(CpuModel::Intel80386)
+            .into_iter()
+            .map(|candidate| candidate.bytes)
+            .collect()
+    }
+
+    /// The bytes the instruction was decoded from, past its prefixes.
+    fn original_form(&self) -> Option<&[Byte]> {
+        let bytes = self.encoding.bytes();
+        let opcode = bytes
+            .iter()
+            .position(|&value| !Prefixes::is_prefix(value, CpuModel::Intel80386))?;
+
+        Some(&bytes[opcode..])
+    }
+
+    fn encode_error(&self) -> EncodeError {
+        EncodeError {
+            instruction: self.to_string(),
+        }
+    }
+
+    fn candidates(&self, model: CpuModel) -> Vec<Candidate> {
+        let table = Table::get();
+        let operands: Vec<Operand> = self.operands().map(normalized).collect();
+        let mut candidates = Vec::new();
+
+        for (spec, layout) in ENCODINGS.iter().zip(&table.layouts) {
+            if spec.op != self.op || spec.since > model || layout.operands.len() != operands.len() {
+                continue;
+            }
+
+            let is_wide = layout.is_wide || (layout.field(Field::Wide).is_some() && self.is_wide);
+
+            for direction in choices(layout, Field::Direction) {
+                for sign in choices(layout, Field::SignExtend) {
+                    let mut fixed = Assignment::fields(&[(Field::Wide, Byte::from(is_wide))]);
+                    let mut operands = operands.clone();
+
+                    if let Some(direction) = direction {
+                        fixed.fields.push((Field::Direction, direction));
+
+                        if direction == 1 {
+                            operands.swap(0, 1);
+                        }
+                    }
+
+                    if let Some(sign) = sign {
+                        fixed.fields.push((Field::SignExtend, sign));
+                    }
+
+                    let mut drafts = vec![fixed];
+
+                    for (&slot, &operand) in layout.operands.iter().zip(&operands) {
+                        let options = self.assignments(slot, operand, is_wide);
+
+                        drafts = drafts
+                            .iter()
+                            .flat_map(|draft| {
+                                options.iter().filter_map(move |option| draft.merge(option))
+                            })
+                            .collect();
+                    }
+
+                    candidates.extend(
+                        drafts
+                            .iter()
+                            .filter_map(|draft| self.assemble(layout, draft, is_wide)),
+                    );
+                }
+            }
+        }
+
+        candidates
+    }
+
+    /// The ways `operand` can fill `slot`.
+    fn assignments(&self, slot: OperandSlot, operand: Operand, is_wide: Wide) -> Vec<Assignment> {
+        let register_code = |operand: Operand| match operand {
+            Operand::Register(register)
+                if Register::from(register.code() << 1 | u8::from(is_wide))
+                    .sized(is_wide, self.prefixes)
+                    == register =>
+            {
+                Some(register.code())
+            }
+            _ => None,
+        };
+
+        match (slot, operand) {
+            (OperandSlot::Register, operand) => register_code(operand)
+                .map(|code| Assignment::fields(&[(Field::Register, code)]))
+                .into_iter()
+                .collect(),
+            (OperandSlot::RegisterOrMemory | OperandSlot::Memory, Operand::Memory(address)) => {
+                memory_assignments(address, self.prefixes)
+            }
+            (OperandSlot::RegisterOrMemory, operand) => register_code(operand)
+                .map(|code| {
+                    Assignment::fields(&[(Field::Mode, 0b11), (Field::RegisterOrMemory, code)])
+                })
+                .into_iter()
+                .collect(),
+            (OperandSlot::SegmentRegister, Operand::SegmentRegister(register)) => {
+                vec![Assignment::fields(&[(
+                    Field::SegmentRegister,
+                    register.code(),
+                )])]
+            }
+            (OperandSlot::Accumulator, operand)
+                if operand == normalized(Operand::accumulator(is_wide, self.prefixes)) =>
+            {
+                vec![Assignment::default()]
+            }
+            (OperandSlot::Dx, Operand::Register(Register::Dx))
+            | (OperandSlot::Cl, Operand::Register(Register::Cl)) => vec![Assignment::default()],
+            (OperandSlot::Count, Operand::Register(Register::Cl)) => {
+                vec![Assignment::fields(&[(Field::Variable, 1)])]
+            }
+            (OperandSlot::Count, Operand::Immediate(value)) if i32::from(value) == 1 => {
+                vec![Assignment::fields(&[(Field::Variable, 0)])]
+            }
+            (OperandSlot::Escape, Operand::Immediate(value)) => {
+                match Byte::try_from(i32::from(value)) {
+                    Ok(opcode @ 0..=0b111_111) => vec![Assignment::fields(&[
+                        (Field::Escape, opcode >> 3),
+                        (Field::Register, opcode & 0b111),
+                    ])],
+                    _ => Vec::new(),
+                }
+            }
+            (OperandSlot::Trailing, operand) => vec![Assignment {
+                trailing: vec![operand],
+                ..Default::default()
+            }],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Lays out the bytes of a complete assignment, as long as its trailing operands fit.
+    fn assemble(&self, layout: &Layout, draft: &Assignment, is_wide: Wide) -> Option<Candidate> {
+        let mut leading: Vec<Byte> = layout.fixed.iter().map(|&(_, value)| value).collect();
+
+        for slot in &layout.fields {
+            leading[slot.byte] |= draft.field(slot.field)? << slot.shift;
+        }
+
+        let is_signed = draft.field(Field::SignExtend) == Some(1);
+        let trailing: Vec<_> = layout
+            .trailing
+            .iter()
+            .map(|&trailing| {
+                let size = trailing_size(trailing, is_wide, is_signed, self.prefixes);
+                (trailing, size)
+            })
+            .collect();
+
+        let mut bytes = self.prefixes.bytes();
+        let opcode = bytes.len();
+        bytes.extend(&leading);
+        bytes.extend(&draft.address);
+
+        let length = bytes.len() + trailing.iter().map(|(_, size)| size).sum::<usize>();
+        let immediate_size = trailing
+            .iter()
+            .find(|(trailing, _)| {
+                matches!(
+                    trailing,
+                    Trailing::Data
+                        | Trailing::Data8
+                        | Trailing::IpIncrement
+                        | Trailing::IpIncrement8
+                )
+            })
+            .map(|&(_, size)| size);
+
+        for ((trailing, size), &operand) in trailing.into_iter().zip(&draft.trailing) {
+            let value = match (trailing, operand) {
+                (Trailing::IpIncrement | Trailing::IpIncrement8, Operand::Immediate(value)) => {
+                    i64::from(self.branch_target(value)) - length as i64
+                }
+                (Trailing::Address, Operand::Memory(address)) => {
+                    i64::from(direct_address(address, self.prefixes)?)
+                }
+                (Trailing::FarPointer, Operand::FarPointer(segment, offset)) => {
+                    let offset_size = size - 2;
+
+                    if offset_size == 2 && offset > u32::from(Word::MAX) {
+                        return None;
+                    }
+
+                    bytes.extend(&offset.to_le_bytes()[..offset_size]);
+                    bytes.extend(segment.to_le_bytes());
+                    continue;
+                }
+                (_, Operand::Immediate(value)) => i64::from(i32::from(value)),
+                _ => return None,
+            };
+
+            if !trailing_range(trailing, is_wide, is_signed, size).contains(&value) {
+                return None;
+            }
+
+            bytes.extend(&value.to_le_bytes()[..size]);
+        }
+
+        Some(Candidate {
+            bytes,
+            opcode,
+            has_modrm: layout.has_modrm(),
+            immediate_size,
+        })
+    }
+
+    /// How far past the start of the instruction a relative branch lands. Instructions built by
+    /// hand are taken to be as long as the displacement implies, like when they are displayed.
+    pub(crate) fn branch_target(&self, displacement: ImmediateValue) -> i32 {
+        let length = if self.encoding.is_empty() {
+            match displacement {
+                ImmediateValue::SignedByte(_) => 2,
+                ImmediateValue::SignedWord(_) => 3,
+                ImmediateValue::SignedDoubleWord(_) => 6,
+            }
+        } else {
+            self.encoding.len() as i32
+        };
+
+        length + i32::from(displacement)
+    }
+}
+
+impl Instructions {
+    /// Encodes the instruction into machine code, picking between its encodings by `choice`.
+    pub fn encode(&self, choice: EncodingChoice) -> Result<Vec<Byte>, EncodeError> {
+        Instruction::from(self).encode(choice)
+    }
+}
+
+/// Both values of a bit the encoding leaves to the encoder, or nothing if it has no such bit.
+fn choices(layout: &Layout, field: Field) -> Vec<Option<Byte>> {
+    match layout.field(field) {
+        Some(_) => vec![Some(0), Some(1)],
+        None => vec![None],
+    }
+}
+
+/// Spells the accumulator the way registers decoded out of a ModRM byte are.
+fn normalized(operand: Operand) -> Operand {
+    match operand {
+        Operand::Accumulator => Operand::Register(Register::Al),
+        Operand::AccumulatorWide => Operand::Register(Register::Ax),
+        operand => operand,
+    }
+}
+
+fn memory_assignments(address: EffectiveAddress, prefixes: Prefixes) -> Vec<Assignment> {
+    address
+        .encodings(prefixes)
+        .into_iter()
+        .map(|encoding| Assignment {
+            fields: vec![
+                (Field::Mode, encoding.mode),
+                (Field::RegisterOrMemory, encoding.register_or_memory),
+            ],
+            address: encoding.bytes,
+            trailing: Vec::new(),
+        })
+        .collect()
+}
+
+/// The address of the accumulator moves, which only takes a displacement.
+fn direct_address(address: EffectiveAddress, prefixes: Prefixes) -> Option<u32> {
+    match address {
+        EffectiveAddress::DirectAddress(address) if !prefixes.address_size_override => {
+            Some(u32::from(address))
+        }
+        EffectiveAddress::Extended(ExtendedAddress {
+            base: None,
+            index: None,
+            displacement: Displacement::DoubleWord(address),
+        }) if prefixes.address_size_override => Some(address as u32),
+        _ => None,
+    }
+}
+
+fn trailing_size(trailing: Trailing, is_wide: Wide, is_signed: bool, prefixes: Prefixes) -> usize {
+    let wide = if prefixes.operand_size_override { 4 } else { 2 };
+
+    match trailing {
+        Trailing::Data if !is_wide || is_signed => 1,
+        Trailing::Data | Trailing::IpIncrement => wide,
+        Trailing::Data8 | Trailing::UnsignedData8 | Trailing::IpIncrement8 => 1,
+        Trailing::Data16 => 2,
+        Trailing::Address if prefixes.address_size_override => 4,
+        Trailing::Address => 2,
+        Trailing::FarPointer => wide + 2,
+    }
+}
+
+/// The values a trailing field of `size` bytes holds. Unsigned values are let through wherever
+/// the field isn't sign extended.
+fn trailing_range(
+    trailing: Trailing,
+    is_wide: Wide,
+    is_signed: bool,
+    size: usize,
+) -> RangeInclusive<i64> {
+    let signed = match size {
+        1 => i64::from(i8::MIN)..=i64::from(i8::MAX),
+        2 => i64::from(i16::MIN)..=i64::from(i16::MAX),
+        _ => i64::from(i32::MIN)..=i64::from(i32::MAX),
+    };
+    let unsigned_end = (1i64 << (size * 8)) - 1;
+
+    match trailing {
+        Trailing::Data if is_wide && is_signed => signed,
+        Trailing::Data8 | Trailing::IpIncrement8 | Trailing::IpIncrement => signed,
+        Trailing::UnsignedData8 | Trailing::Address => 0..=unsigned_end,
+        Trailing::Data | Trailing::Data16 | Trailing::FarPointer => *signed.start()..=unsigned_end,
+    }
+}
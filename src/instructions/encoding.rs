@@ -0,0 +1,57 @@
+use crate::cursor::ByteCursor;
+use crate::error::{DecodeErrorReason, DecodeResult};
+
+/// The most bytes an instruction can span, prefixes included, before the 80386 faults on it.
+pub const MAX_INSTRUCTION_LENGTH: usize = 15;
+
+/// Where a decoded instruction starts in the program, along with the bytes it was decoded from.
+/// Instructions built by hand have none.
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct Encoding {
+    offset: u64,
+    length: u8,
+    bytes: [u8; MAX_INSTRUCTION_LENGTH],
+}
+
+impl Encoding {
+    /// Rewinds over the instruction that just finished decoding at the reader's position to
+    /// copy its bytes, ending back where it started.
+    pub(crate) fn read_back(reader: &mut impl ByteCursor, offset: u64) -> DecodeResult<Self> {
+        let end = reader.stream_position()?;
+        let length = usize::try_from(end - offset)
+            .ok()
+            .filter(|length| *length <= MAX_INSTRUCTION_LENGTH)
+            .ok_or(DecodeErrorReason::InstructionTooLong)?;
+
+        let mut bytes = [0; MAX_INSTRUCTION_LENGTH];
+        reader.seek_relative(-(length as i64))?;
+        reader.read_exact(&mut bytes[..length])?;
+
+        Ok(Encoding {
+            offset,
+            length: length as u8,
+            bytes,
+        })
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn len(&self) -> usize {
+        usize::from(self.length)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.len()]
+    }
+
+    /// The offset of the next instruction, which relative jumps and calls count from.
+    pub fn end(&self) -> u64 {
+        self.offset + u64::from(self.length)
+    }
+}
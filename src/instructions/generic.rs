@@ -0,0 +1,510 @@
+use crate::instructions::decode::Instructions;
+use crate::instructions::encoding::Encoding;
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::prefix::{Prefixes, Repeat};
+use crate::memory::EffectiveAddress;
+use crate::Wide;
+use std::fmt::{Display, Formatter};
+
+/// Every operation the decoder knows, named after its mnemonic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Op {
+    Mov,
+    Add,
+    Adc,
+    Sub,
+    Sbb,
+    Cmp,
+    And,
+    Or,
+    Xor,
+    Test,
+    Inc,
+    Dec,
+    Neg,
+    Not,
+    Mul,
+    Imul,
+    Div,
+    Idiv,
+    Aaa,
+    Daa,
+    Aas,
+    Das,
+    Aam,
+    Aad,
+    Bound,
+    Shl,
+    Shr,
+    Sar,
+    Rol,
+    Ror,
+    Rcl,
+    Rcr,
+    Movs,
+    Cmps,
+    Scas,
+    Lods,
+    Stos,
+    Ins,
+    Outs,
+    Push,
+    Pop,
+    Pushf,
+    Popf,
+    Pusha,
+    Popa,
+    Enter,
+    Leave,
+    Xchg,
+    Xlat,
+    Lea,
+    Lds,
+    Les,
+    Lahf,
+    Sahf,
+    Cbw,
+    Cwd,
+    In,
+    Out,
+    Je,
+    Jl,
+    Jle,
+    Jb,
+    Jbe,
+    Jp,
+    Jo,
+    Js,
+    Jne,
+    Jnl,
+    Jnle,
+    Jnb,
+    Jnbe,
+    Jnp,
+    Jno,
+    Jns,
+    Loop,
+    Loope,
+    Loopne,
+    Jcxz,
+    Jmp,
+    /// A jump to another segment, either to a far pointer or through one in memory.
+    JmpFar,
+    Call,
+    /// A call into another segment, either to a far pointer or through one in memory.
+    CallFar,
+    Ret,
+    Retf,
+    Int,
+    Int3,
+    Into,
+    Iret,
+    Clc,
+    Cmc,
+    Stc,
+    Cld,
+    Std,
+    Cli,
+    Sti,
+    Hlt,
+    Wait,
+    Esc,
+    Nop,
+}
+
+impl Op {
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Op::Mov => "mov",
+            Op::Add => "add",
+            Op::Adc => "adc",
+            Op::Sub => "sub",
+            Op::Sbb => "sbb",
+            Op::Cmp => "cmp",
+            Op::And => "and",
+            Op::Or => "or",
+            Op::Xor => "xor",
+            Op::Test => "test",
+            Op::Inc => "inc",
+            Op::Dec => "dec",
+            Op::Neg => "neg",
+            Op::Not => "not",
+            Op::Mul => "mul",
+            Op::Imul => "imul",
+            Op::Div => "div",
+            Op::Idiv => "idiv",
+            Op::Aaa => "aaa",
+            Op::Daa => "daa",
+            Op::Aas => "aas",
+            Op::Das => "das",
+            Op::Aam => "aam",
+            Op::Aad => "aad",
+            Op::Bound => "bound",
+            Op::Shl => "shl",
+            Op::Shr => "shr",
+            Op::Sar => "sar",
+            Op::Rol => "rol",
+            Op::Ror => "ror",
+            Op::Rcl => "rcl",
+            Op::Rcr => "rcr",
+            Op::Movs => "movs",
+            Op::Cmps => "cmps",
+            Op::Scas => "scas",
+            Op::Lods => "lods",
+            Op::Stos => "stos",
+            Op::Ins => "ins",
+            Op::Outs => "outs",
+            Op::Push => "push",
+            Op::Pop => "pop",
+            Op::Pushf => "pushf",
+            Op::Popf => "popf",
+            Op::Pusha => "pusha",
+            Op::Popa => "popa",
+            Op::Enter => "enter",
+            Op::Leave => "leave",
+            Op::Xchg => "xchg",
+            Op::Xlat => "xlat",
+            Op::Lea => "lea",
+            Op::Lds => "lds",
+            Op::Les => "les",
+            Op::Lahf => "lahf",
+            Op::Sahf => "sahf",
+            Op::Cbw => "cbw",
+            Op::Cwd => "cwd",
+            Op::In => "in",
+            Op::Out => "out",
+            Op::Je => "je",
+            Op::Jl => "jl",
+            Op::Jle => "jle",
+            Op::Jb => "jb",
+            Op::Jbe => "jbe",
+            Op::Jp => "jp",
+            Op::Jo => "jo",
+            Op::Js => "js",
+            Op::Jne => "jne",
+            Op::Jnl => "jnl",
+            Op::Jnle => "jnle",
+            Op::Jnb => "jnb",
+            Op::Jnbe => "jnbe",
+            Op::Jnp => "jnp",
+            Op::Jno => "jno",
+            Op::Jns => "jns",
+            Op::Loop => "loop",
+            Op::Loope => "loope",
+            Op::Loopne => "loopne",
+            Op::Jcxz => "jcxz",
+            Op::Jmp | Op::JmpFar => "jmp",
+            Op::Call | Op::CallFar => "call",
+            Op::Ret => "ret",
+            Op::Retf => "retf",
+            Op::Int => "int",
+            Op::Int3 => "int3",
+            Op::Into => "into",
+            Op::Iret => "iret",
+            Op::Clc => "clc",
+            Op::Cmc => "cmc",
+            Op::Stc => "stc",
+            Op::Cld => "cld",
+            Op::Std => "std",
+            Op::Cli => "cli",
+            Op::Sti => "sti",
+            Op::Hlt => "hlt",
+            Op::Wait => "wait",
+            Op::Esc => "esc",
+            Op::Nop => "nop",
+        }
+    }
+
+    /// The string operations, whose mnemonic takes the operand width as a suffix and which
+    /// repeat under the rep prefixes.
+    pub fn is_string(&self) -> bool {
+        matches!(
+            self,
+            Op::Movs | Op::Cmps | Op::Scas | Op::Lods | Op::Stos | Op::Ins | Op::Outs
+        )
+    }
+
+    /// The operations whose immediate operand is a displacement from the next instruction.
+    pub fn is_relative_branch(&self) -> bool {
+        matches!(
+            self,
+            Op::Je
+                | Op::Jl
+                | Op::Jle
+                | Op::Jb
+                | Op::Jbe
+                | Op::Jp
+                | Op::Jo
+                | Op::Js
+                | Op::Jne
+                | Op::Jnl
+                | Op::Jnle
+                | Op::Jnb
+                | Op::Jnbe
+                | Op::Jnp
+                | Op::Jno
+                | Op::Jns
+                | Op::Loop
+                | Op::Loope
+                | Op::Loopne
+                | Op::Jcxz
+                | Op::Jmp
+                | Op::Call
+        )
+    }
+
+    /// Single operand operations (and shifts), whose memory operand carries no register to
+    /// infer the operation width from, so it has to be spelled out for the assembler.
+    pub(crate) fn sizes_memory_operand(&self) -> bool {
+        matches!(
+            self,
+            Op::Inc
+                | Op::Dec
+                | Op::Neg
+                | Op::Not
+                | Op::Mul
+                | Op::Imul
+                | Op::Div
+                | Op::Idiv
+                | Op::Shl
+                | Op::Shr
+                | Op::Sar
+                | Op::Rol
+                | Op::Ror
+                | Op::Rcl
+                | Op::Rcr
+                | Op::Push
+                | Op::Pop
+        )
+    }
+
+    /// Operations that move an immediate into memory, which has to be given a width.
+    pub(crate) fn sizes_immediate_into_memory(&self) -> bool {
+        matches!(
+            self,
+            Op::Mov
+                | Op::Add
+                | Op::Adc
+                | Op::Sub
+                | Op::Sbb
+                | Op::Cmp
+                | Op::And
+                | Op::Or
+                | Op::Xor
+                | Op::Test
+        )
+    }
+
+    fn is_comparison(&self) -> bool {
+        matches!(self, Op::Cmps | Op::Scas)
+    }
+}
+
+/// A decoded instruction, whatever its operation. Tools can match on its [`Op`] and operands
+/// without knowing how each family of instructions is decoded or simulated.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Instruction {
+    pub op: Op,
+    pub prefixes: Prefixes,
+    /// The operands in the order they are written, destination first, with the unused slots
+    /// left at the end.
+    pub operands: [Option<Operand>; 3],
+    pub is_wide: Wide,
+    pub encoding: Encoding,
+}
+
+impl Instruction {
+    pub fn operands(&self) -> impl Iterator<Item = Operand> + '_ {
+        self.operands.iter().flatten().copied()
+    }
+
+    /// Whether a word operation operates on double words instead, under an operand size prefix.
+    pub fn is_double_word(&self) -> bool {
+        self.prefixes.is_double_word(self.is_wide)
+    }
+
+    /// Whether a 32-bit register or immediate among the operands implies the operand size prefix.
+    fn shows_double_word(&self) -> bool {
+        self.operands().any(|operand| match operand {
+            Operand::Register(register) => register.is_extended(),
+            Operand::Immediate(value) => matches!(value, ImmediateValue::SignedDoubleWord(_)),
+            _ => false,
+        })
+    }
+
+    /// Whether a 32-bit memory operand implies the address size prefix.
+    fn shows_extended_address(&self) -> bool {
+        self.operands()
+            .any(|operand| matches!(operand, Operand::Memory(EffectiveAddress::Extended(_))))
+    }
+
+    /// Whether a memory operand carries the segment override.
+    fn shows_segment_override(&self) -> bool {
+        self.operands()
+            .any(|operand| matches!(operand, Operand::Memory(_)))
+    }
+
+    fn fmt_operand(
+        &self,
+        index: usize,
+        operand: Operand,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match operand {
+            Operand::Immediate(value) if self.op.is_relative_branch() => {
+                self.fmt_relative_branch(value, f)
+            }
+            Operand::Immediate(value) => {
+                let is_into_memory = matches!(self.operands[0], Some(Operand::Memory(_)))
+                    && self.op.sizes_immediate_into_memory();
+
+                // Pushed immediates keep the width they were encoded with, as the byte form
+                // is sign extended.
+                if is_into_memory || self.op == Op::Push {
+                    f.write_str(match value {
+                        ImmediateValue::SignedByte(_) => "byte ",
+                        ImmediateValue::SignedWord(_) => "word ",
+                        ImmediateValue::SignedDoubleWord(_) => "dword ",
+                    })?;
+                }
+
+                value.fmt(f)
+            }
+            Operand::Memory(_) => {
+                if matches!(self.op, Op::JmpFar | Op::CallFar) {
+                    f.write_str("far ")?;
+                } else if index == 0 && self.op.sizes_memory_operand() {
+                    f.write_str(if self.is_double_word() {
+                        "dword "
+                    } else if self.is_wide {
+                        "word "
+                    } else {
+                        "byte "
+                    })?;
+                }
+
+                if let Some(segment) = self.prefixes.segment_override {
+                    f.write_fmt(format_args!("{segment}:"))?;
+                }
+
+                operand.fmt(f)
+            }
+            operand => operand.fmt(f),
+        }
+    }
+
+    fn fmt_relative_branch(
+        &self,
+        value: ImmediateValue,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        // nasm resolves `$` to the start of the instruction, prefixes included, while the
+        // displacement is relative to its end.
+        let displacement = i64::from(i32::from(value));
+
+        match value {
+            // nasm picks the short form for any target in reach of it, so keeping the near
+            // encoding has to be asked for explicitly.
+            ImmediateValue::SignedWord(_)
+                if self.op == Op::Jmp && i8::try_from(displacement + 1).is_ok() =>
+            {
+                f.write_str("near ")?;
+            }
+            ImmediateValue::SignedDoubleWord(_) => f.write_str("dword ")?,
+            _ => {}
+        }
+
+        let computed_displacement = self.branch_target(value);
+        let sign = if computed_displacement.is_negative() {
+            "-"
+        } else {
+            "+"
+        };
+
+        f.write_fmt(format_args!(
+            "${sign}{}",
+            computed_displacement.unsigned_abs()
+        ))
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.prefixes.lock {
+            f.write_str("lock ")?;
+        }
+
+        // Size prefixes only need spelling out when no operand already implies them.
+        if self.prefixes.operand_size_override && !self.shows_double_word() {
+            f.write_str("o32 ")?;
+        }
+
+        if self.prefixes.address_size_override && !self.shows_extended_address() {
+            f.write_str("a32 ")?;
+        }
+
+        // Without a memory operand to go with, as for string operations and xlat, the override is
+        // written like the other prefixes.
+        if let Some(segment) = self.prefixes.segment_override {
+            if !self.shows_segment_override() {
+                f.write_fmt(format_args!("{segment} "))?;
+            }
+        }
+
+        if self.op.is_string() {
+            match (self.prefixes.repeat, self.op.is_comparison()) {
+                (Some(Repeat::WhileZero), false) => f.write_str("rep ")?,
+                (Some(Repeat::WhileZero), true) => f.write_str("repe ")?,
+                (Some(Repeat::WhileNotZero), _) => f.write_str("repne ")?,
+                (None, _) => {}
+            }
+
+            f.write_str(self.op.mnemonic())?;
+            return f.write_str(if self.is_wide { "w" } else { "b" });
+        }
+
+        f.write_str(self.op.mnemonic())?;
+
+        for (index, operand) in self.operands().enumerate() {
+            // The base of aam and aad is implied when it is 10.
+            if matches!(self.op, Op::Aam | Op::Aad)
+                && operand == Operand::Immediate(ImmediateValue::SignedWord(10))
+            {
+                continue;
+            }
+
+            f.write_str(if index == 0 { " " } else { ", " })?;
+            self.fmt_operand(index, operand, f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&Instructions> for Instruction {
+    fn from(instructions: &Instructions) -> Self {
+        let instruction = instructions.instruction();
+        let op = instructions.op();
+
+        let operands = if instructions.has_implied_operands() {
+            [None, None, None]
+        } else if op == Op::Esc {
+            // The opcode for the coprocessor is written ahead of the operand it is given.
+            [instruction.source, Some(instruction.destination), None]
+        } else {
+            [
+                Some(instruction.destination),
+                instruction.source,
+                instruction.immediate.map(Operand::Immediate),
+            ]
+        };
+
+        Instruction {
+            op,
+            prefixes: instruction.prefixes,
+            operands,
+            is_wide: instruction.is_wide,
+            encoding: instruction.encoding,
+        }
+    }
+}
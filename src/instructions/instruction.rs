@@ -1,11 +1,13 @@
+use crate::cursor::ByteCursor;
 use crate::cycle::EstimatedCycleCount;
-use crate::instructions::operands::Operand;
+use crate::instructions::encoding::Encoding;
 use crate::instructions::operands::Operand::AccumulatorWide;
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::prefix::Prefixes;
 use crate::mode::InstructionMode;
 use crate::store::Store;
 use crate::Wide;
-use std::fs::File;
-use std::io::BufReader;
+use std::io::SeekFrom;
 
 #[derive(Copy, Clone, PartialEq)]
 pub struct AnyInstruction {
@@ -14,6 +16,10 @@ pub struct AnyInstruction {
     pub source: Option<Operand>,
     pub destination: Operand,
     pub clock_penalty: Option<u16>,
+    pub prefixes: Prefixes,
+    /// The trailing immediate of three operand forms, like the 80186 `imul ax, bx, 10`.
+    pub immediate: Option<ImmediateValue>,
+    pub encoding: Encoding,
 }
 
 impl Default for AnyInstruction {
@@ -24,13 +30,29 @@ impl Default for AnyInstruction {
             source: None,
             destination: AccumulatorWide,
             clock_penalty: None,
+            prefixes: Prefixes::default(),
+            immediate: None,
+            encoding: Encoding::default(),
         }
     }
 }
 
-pub trait Instruction
+pub trait Execute
 where
     Self: EstimatedCycleCount,
 {
-    fn execute(&self, reader: &mut BufReader<File>, store: &mut Store) -> u32;
+    fn execute(&self, reader: &mut impl ByteCursor, store: &mut Store) -> u32;
+}
+
+/// The simulator executes straight out of the program, so IP is the reader's position in it.
+pub(crate) fn instruction_pointer(reader: &mut impl ByteCursor) -> u16 {
+    reader
+        .stream_position()
+        .expect("Failed to read instruction pointer") as u16
+}
+
+pub(crate) fn set_instruction_pointer(reader: &mut impl ByteCursor, ip: u16) {
+    reader
+        .seek(SeekFrom::Start(u64::from(ip)))
+        .unwrap_or_else(|_| panic!("Failed to jump to {ip}"));
 }
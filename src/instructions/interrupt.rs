@@ -0,0 +1,139 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::flag_register::FlagRegister;
+use crate::instructions::{instruction_pointer, set_instruction_pointer, AnyInstruction, Execute};
+use crate::segment_register::SegmentRegister;
+use crate::store::{StopReason, Store};
+
+pub enum InterruptInstructions {
+    TypeSpecified(AnyInstruction),
+    Type3(AnyInstruction),
+    OnOverflow(AnyInstruction),
+    Return(AnyInstruction),
+}
+
+impl InterruptInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            InterruptInstructions::TypeSpecified(instruction)
+            | InterruptInstructions::Type3(instruction)
+            | InterruptInstructions::OnOverflow(instruction)
+            | InterruptInstructions::Return(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            InterruptInstructions::TypeSpecified(instruction)
+            | InterruptInstructions::Type3(instruction)
+            | InterruptInstructions::OnOverflow(instruction)
+            | InterruptInstructions::Return(instruction) => instruction,
+        }
+    }
+}
+
+/// Transfers control to the handler for `interrupt_type` found in the interrupt vector table at
+/// 0000:0000. A null vector means nothing handles the interrupt, so the simulation stops there
+/// instead of running whatever happens to be at address zero.
+pub(crate) fn raise_interrupt(reader: &mut impl ByteCursor, store: &mut Store, interrupt_type: u8) {
+    let vector_address = u16::from(interrupt_type) * 4;
+
+    let (offset, _) = store
+        .memory_store()
+        .read_from_memory(0, vector_address, true);
+    let (segment, _) = store
+        .memory_store()
+        .read_from_memory(0, vector_address + 2, true);
+    let (segment, offset): (u16, u16) = (segment.into(), offset.into());
+
+    if segment == 0 && offset == 0 {
+        store.stop(StopReason::UnhandledInterrupt(interrupt_type));
+
+        return;
+    }
+
+    let flags = store.flag_register_store().read_word();
+    let cs = store
+        .segment_register_store()
+        .read_value(SegmentRegister::Cs);
+    let ip = instruction_pointer(reader);
+
+    store.push_word(flags);
+    store.push_word(cs);
+    store.push_word(ip);
+
+    let flag_register_store = store.flag_register_store_mut();
+    flag_register_store.unset_flag(FlagRegister::Interrupt);
+    flag_register_store.unset_flag(FlagRegister::Trap);
+
+    store
+        .segment_register_store_mut()
+        .write_word_to_segment_register(SegmentRegister::Cs, segment);
+    set_instruction_pointer(reader, offset);
+}
+
+impl EstimatedCycleCount for InterruptInstructions {
+    fn num_cycles(&self) -> u32 {
+        match self {
+            InterruptInstructions::TypeSpecified(_) => 51,
+            InterruptInstructions::Type3(_) => 52,
+            // into only costs the full 53 cycles when the interrupt is taken, see `execute`.
+            InterruptInstructions::OnOverflow(_) => 4,
+            InterruptInstructions::Return(_) => 24,
+        }
+    }
+}
+
+impl Execute for InterruptInstructions {
+    fn execute(&self, reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        match self {
+            InterruptInstructions::TypeSpecified(AnyInstruction { destination, .. }) => {
+                let (interrupt_type, _) = destination.to_immediate_value(true, store);
+                let interrupt_type: u16 = interrupt_type.into();
+
+                raise_interrupt(reader, store, interrupt_type as u8);
+
+                self.num_cycles()
+            }
+            InterruptInstructions::Type3(_) => {
+                raise_interrupt(reader, store, 3);
+
+                self.num_cycles()
+            }
+            InterruptInstructions::OnOverflow(_) => {
+                if store.flag_register_store().get_flag(FlagRegister::Overflow) {
+                    raise_interrupt(reader, store, 4);
+
+                    53
+                } else {
+                    self.num_cycles()
+                }
+            }
+            InterruptInstructions::Return(_) => {
+                if store.is_outermost_return() {
+                    store.stop(StopReason::Returned);
+
+                    return self.num_cycles();
+                }
+
+                let (ip, ip_clock_penalty) = store.pop_word();
+                let (cs, cs_clock_penalty) = store.pop_word();
+                let (flags, flags_clock_penalty) = store.pop_word();
+
+                store
+                    .segment_register_store_mut()
+                    .write_word_to_segment_register(SegmentRegister::Cs, cs);
+                store.flag_register_store_mut().write_word(flags);
+                set_instruction_pointer(reader, ip);
+
+                let clock_penalty = [ip_clock_penalty, cs_clock_penalty, flags_clock_penalty]
+                    .into_iter()
+                    .filter(|&clock_penalty| clock_penalty)
+                    .count() as u32
+                    * 4;
+
+                self.num_cycles() + clock_penalty
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::{AnyInstruction, Execute};
+use crate::register::Register;
+use crate::store::Store;
+
+pub enum InputOutputInstructions {
+    Input(AnyInstruction),
+    Output(AnyInstruction),
+}
+
+impl InputOutputInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            InputOutputInstructions::Input(instruction)
+            | InputOutputInstructions::Output(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            InputOutputInstructions::Input(instruction)
+            | InputOutputInstructions::Output(instruction) => instruction,
+        }
+    }
+}
+
+impl EstimatedCycleCount for InputOutputInstructions {
+    fn num_cycles(&self) -> u32 {
+        let instruction = self.instruction();
+
+        let port = match self {
+            InputOutputInstructions::Input(_) => instruction.source.unwrap(),
+            InputOutputInstructions::Output(_) => instruction.destination,
+        };
+
+        match port {
+            Operand::Immediate(_) => 10,
+            Operand::Register(Register::Dx) => 8,
+            _ => panic!("Invalid port for in or out"),
+        }
+    }
+}
+
+impl Execute for InputOutputInstructions {
+    fn execute(&self, _reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        let instruction = self.instruction();
+        let is_wide = instruction.is_wide;
+
+        let port = match self {
+            InputOutputInstructions::Input(_) => instruction.source.unwrap(),
+            InputOutputInstructions::Output(_) => instruction.destination,
+        };
+        let (port, _) = port.to_immediate_value(true, store);
+        let port: u16 = port.into();
+
+        match self {
+            InputOutputInstructions::Input(AnyInstruction { destination, .. }) => {
+                let value = if is_wide {
+                    ImmediateValue::SignedWord(store.io_bus_mut().read_word(port) as i16)
+                } else {
+                    ImmediateValue::SignedByte(store.io_bus_mut().read_byte(port) as i8)
+                };
+
+                destination.write_value(value, is_wide, store);
+            }
+            InputOutputInstructions::Output(AnyInstruction { source, .. }) => {
+                let (value, _) = source
+                    .expect("out expects a source")
+                    .to_immediate_value(is_wide, store);
+
+                if is_wide {
+                    store.io_bus_mut().write_word(port, value.into());
+                } else {
+                    store.io_bus_mut().write_byte(
+                        port,
+                        value.try_into().expect("Not is wide but value is word"),
+                    );
+                }
+            }
+        }
+
+        // Like memory, a word on an odd port takes two bus cycles.
+        self.num_cycles()
+            + if is_wide && !port.is_multiple_of(2) {
+                4
+            } else {
+                0
+            }
+    }
+}
@@ -1,14 +1,12 @@
+use crate::cursor::ByteCursor;
 use crate::cycle::EstimatedCycleCount;
 use crate::flag_register::FlagRegister;
-use crate::instructions::operands::{ImmediateValue, Operand};
-use crate::instructions::{AnyInstruction, Instruction};
+use crate::instructions::operands::Operand;
+use crate::instructions::{instruction_pointer, set_instruction_pointer, AnyInstruction, Execute};
 use crate::prelude::*;
 use crate::register::Register::Cx;
+use crate::segment_register::SegmentRegister;
 use crate::store::Store;
-use byteorder::ReadBytesExt;
-use std::fmt::{Display, Formatter};
-use std::fs::File;
-use std::io::{BufReader, Seek};
 
 #[derive(Copy, Clone, PartialEq)]
 #[allow(clippy::enum_variant_names)]
@@ -33,325 +31,79 @@ pub enum JumpInstructions {
     LoopWhileZeroOrEqual(AnyInstruction),
     LoopWhileNotZeroAndNotEqual(AnyInstruction),
     JumpOnCxZero(AnyInstruction),
+    JumpDirectWithinSegment(AnyInstruction),
+    JumpDirectWithinSegmentShort(AnyInstruction),
+    JumpIndirectWithinSegment(AnyInstruction),
+    JumpDirectIntersegment(AnyInstruction),
+    JumpIndirectIntersegment(AnyInstruction),
 }
 
-impl Display for JumpInstructions {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        fn write_jump_instruction(
-            opcode: &str,
-            displacement: i8,
-            f: &mut Formatter<'_>,
-        ) -> std::fmt::Result {
-            f.write_str(opcode)?;
-
-            let computed_displacement = 2 + displacement;
-            let sign = if computed_displacement.is_negative() {
-                "-"
-            } else {
-                "+"
-            };
-
-            f.write_fmt(format_args!(" ${sign}{}", computed_displacement.abs()))
-        }
-
-        match self {
-            JumpInstructions::JumpOnEqualOrZero(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("je", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnLess(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jl", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnLessOrEqual(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jle", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnBelow(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jb", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnBelowOrEqual(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jbe", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnParityEven(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jp", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnOverflow(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jo", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnSign(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("js", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnNotEqualAndNotZero(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jne", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnNotLess(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jnl", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnNotLessAndNotEqual(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jnle", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnNotBelow(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jnb", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnNotBelowAndNotEqual(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jnbe", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnParityOdd(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jnp", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnNotOverflow(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jno", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnNotSign(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jns", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::Loop(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("loop", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::LoopWhileZeroOrEqual(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("loope", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::LoopWhileNotZeroAndNotEqual(AnyInstruction {
-                destination, ..
-            }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("loopne", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-            JumpInstructions::JumpOnCxZero(AnyInstruction { destination, .. }) => {
-                if let Operand::Immediate(ImmediateValue::SignedByte(displacement)) = destination {
-                    write_jump_instruction("jcxz", *displacement, f)
-                } else {
-                    Ok(())
-                }
-            }
-        }
-    }
+pub(crate) fn far_offset(offset: u32) -> Word {
+    Word::try_from(offset).expect("32-bit offsets are only decoded, not simulated")
 }
 
 impl JumpInstructions {
-    pub fn is_jump_instruction(value: Byte) -> bool {
-        bit_match!(value, (0, 1, 1, 1, 0, 1, 0, 0))
-            || bit_match!(value, (0, 1, 1, 1, 1, 1, 0, 0))
-            || bit_match!(value, (0, 1, 1, 1, 1, 1, 1, 0))
-            || bit_match!(value, (0, 1, 1, 1, 0, 0, 1, 0))
-            || bit_match!(value, (0, 1, 1, 1, 0, 1, 1, 0))
-            || bit_match!(value, (0, 1, 1, 1, 1, 0, 1, 0))
-            || bit_match!(value, (0, 1, 1, 1, 0, 0, 0, 0))
-            || bit_match!(value, (0, 1, 1, 1, 1, 0, 0, 0))
-            || bit_match!(value, (0, 1, 1, 1, 0, 1, 0, 1))
-            || bit_match!(value, (0, 1, 1, 1, 1, 1, 0, 1))
-            || bit_match!(value, (0, 1, 1, 1, 1, 1, 1, 1))
-            || bit_match!(value, (0, 1, 1, 1, 0, 0, 1, 1))
-            || bit_match!(value, (0, 1, 1, 1, 0, 1, 1, 1))
-            || bit_match!(value, (0, 1, 1, 1, 1, 0, 1, 1))
-            || bit_match!(value, (0, 1, 1, 1, 0, 0, 0, 1))
-            || bit_match!(value, (0, 1, 1, 1, 1, 0, 0, 1))
-            || bit_match!(value, (1, 1, 1, 0, 0, 0, 1, 0))
-            || bit_match!(value, (1, 1, 1, 0, 0, 0, 0, 1))
-            || bit_match!(value, (1, 1, 1, 0, 0, 0, 0, 0))
-            || bit_match!(value, (1, 1, 1, 0, 0, 0, 1, 1))
-    }
-}
+    pub fn instruction(&self) -> &AnyInstruction {
+        use JumpInstructions::*;
 
-impl TryFrom<(Byte, &mut BufReader<File>)> for JumpInstructions {
-    type Error = BoxDynError;
+        match self {
+            JumpOnEqualOrZero(instruction)
+            | JumpOnLess(instruction)
+            | JumpOnLessOrEqual(instruction)
+            | JumpOnBelow(instruction)
+            | JumpOnBelowOrEqual(instruction)
+            | JumpOnParityEven(instruction)
+            | JumpOnOverflow(instruction)
+            | JumpOnSign(instruction)
+            | JumpOnNotEqualAndNotZero(instruction)
+            | JumpOnNotLess(instruction)
+            | JumpOnNotLessAndNotEqual(instruction)
+            | JumpOnNotBelow(instruction)
+            | JumpOnNotBelowAndNotEqual(instruction)
+            | JumpOnParityOdd(instruction)
+            | JumpOnNotOverflow(instruction)
+            | JumpOnNotSign(instruction)
+            | Loop(instruction)
+            | LoopWhileZeroOrEqual(instruction)
+            | LoopWhileNotZeroAndNotEqual(instruction)
+            | JumpOnCxZero(instruction)
+            | JumpDirectWithinSegment(instruction)
+            | JumpDirectWithinSegmentShort(instruction)
+            | JumpIndirectWithinSegment(instruction)
+            | JumpDirectIntersegment(instruction)
+            | JumpIndirectIntersegment(instruction) => instruction,
+        }
+    }
 
-    fn try_from((value, reader): (Byte, &mut BufReader<File>)) -> Result<Self, Self::Error> {
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
         use JumpInstructions::*;
 
-        match value {
-            value if bit_match!(value, (0, 1, 1, 1, 0, 1, 0, 0)) => {
-                Ok(JumpOnEqualOrZero(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 1, 1, 0, 0)) => {
-                Ok(JumpOnLess(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 1, 1, 1, 0)) => {
-                Ok(JumpOnLessOrEqual(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 0, 0, 1, 0)) => {
-                Ok(JumpOnBelow(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 0, 1, 1, 0)) => {
-                Ok(JumpOnBelowOrEqual(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 1, 0, 1, 0)) => {
-                Ok(JumpOnParityEven(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 0, 0, 0, 0)) => {
-                Ok(JumpOnOverflow(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 1, 0, 0, 0)) => {
-                Ok(JumpOnSign(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 0, 1, 0, 1)) => {
-                Ok(JumpOnNotEqualAndNotZero(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 1, 1, 0, 1)) => {
-                Ok(JumpOnNotLess(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 1, 1, 1, 1)) => {
-                Ok(JumpOnNotLessAndNotEqual(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 0, 0, 1, 1)) => {
-                Ok(JumpOnNotBelow(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 0, 1, 1, 1)) => {
-                Ok(JumpOnNotBelowAndNotEqual(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 1, 0, 1, 1)) => {
-                Ok(JumpOnParityOdd(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 0, 0, 0, 1)) => {
-                Ok(JumpOnNotOverflow(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (0, 1, 1, 1, 1, 0, 0, 1)) => {
-                Ok(JumpOnNotSign(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (1, 1, 1, 0, 0, 0, 1, 0)) => Ok(Loop(AnyInstruction {
-                destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                ..Default::default()
-            })),
-            value if bit_match!(value, (1, 1, 1, 0, 0, 0, 0, 1)) => {
-                Ok(LoopWhileZeroOrEqual(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (1, 1, 1, 0, 0, 0, 0, 0)) => {
-                Ok(LoopWhileNotZeroAndNotEqual(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            value if bit_match!(value, (1, 1, 1, 0, 0, 0, 1, 1)) => {
-                Ok(JumpOnCxZero(AnyInstruction {
-                    destination: Operand::Immediate(ImmediateValue::SignedByte(reader.read_i8()?)),
-                    ..Default::default()
-                }))
-            }
-            _ => Err("Not a jump instruction".into()),
+        match self {
+            JumpOnEqualOrZero(instruction)
+            | JumpOnLess(instruction)
+            | JumpOnLessOrEqual(instruction)
+            | JumpOnBelow(instruction)
+            | JumpOnBelowOrEqual(instruction)
+            | JumpOnParityEven(instruction)
+            | JumpOnOverflow(instruction)
+            | JumpOnSign(instruction)
+            | JumpOnNotEqualAndNotZero(instruction)
+            | JumpOnNotLess(instruction)
+            | JumpOnNotLessAndNotEqual(instruction)
+            | JumpOnNotBelow(instruction)
+            | JumpOnNotBelowAndNotEqual(instruction)
+            | JumpOnParityOdd(instruction)
+            | JumpOnNotOverflow(instruction)
+            | JumpOnNotSign(instruction)
+            | Loop(instruction)
+            | LoopWhileZeroOrEqual(instruction)
+            | LoopWhileNotZeroAndNotEqual(instruction)
+            | JumpOnCxZero(instruction)
+            | JumpDirectWithinSegment(instruction)
+            | JumpDirectWithinSegmentShort(instruction)
+            | JumpIndirectWithinSegment(instruction)
+            | JumpDirectIntersegment(instruction)
+            | JumpIndirectIntersegment(instruction) => instruction,
         }
     }
 }
@@ -379,12 +131,28 @@ impl EstimatedCycleCount for JumpInstructions {
             JumpInstructions::LoopWhileZeroOrEqual(AnyInstruction { .. }) => 16,
             JumpInstructions::LoopWhileNotZeroAndNotEqual(AnyInstruction { .. }) => 16,
             JumpInstructions::JumpOnCxZero(AnyInstruction { .. }) => 16,
+            JumpInstructions::JumpDirectWithinSegment(_)
+            | JumpInstructions::JumpDirectWithinSegmentShort(_) => 15,
+            JumpInstructions::JumpIndirectWithinSegment(instruction) => {
+                match instruction.destination {
+                    Operand::Register(_) => 11,
+                    Operand::Memory(ea) => 18 + ea.num_cycles(),
+                    _ => panic!("Invalid jmp operation"),
+                }
+            }
+            JumpInstructions::JumpDirectIntersegment(_) => 15,
+            JumpInstructions::JumpIndirectIntersegment(instruction) => {
+                match instruction.destination {
+                    Operand::Memory(ea) => 24 + ea.num_cycles(),
+                    _ => panic!("Invalid jmp operation"),
+                }
+            }
         }
     }
 }
 
-impl Instruction for JumpInstructions {
-    fn execute(&self, reader: &mut BufReader<File>, store: &mut Store) -> u32 {
+impl Execute for JumpInstructions {
+    fn execute(&self, reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
         use FlagRegister::*;
 
         let (should_jump, (displacement, clock_penalty)) = match self {
@@ -491,12 +259,14 @@ impl Instruction for JumpInstructions {
                 (!store.flag_register_store().get_flag(Sign), displacement)
             }
             JumpInstructions::Loop(AnyInstruction { destination, .. }) => {
-                let cx_value: i16 = store.register_store().read_value(Cx).into();
-                let cx_value = cx_value - 1;
+                let cx_value = store
+                    .register_store()
+                    .read_word_from_register(Cx)
+                    .wrapping_sub(1);
 
                 store
                     .register_store_mut()
-                    .write_word_to_register(Cx, ImmediateValue::SignedWord(cx_value).into());
+                    .write_word_to_register(Cx, cx_value);
 
                 let should_jump = cx_value != 0;
                 let displacement = destination.to_immediate_value(true, store);
@@ -504,12 +274,14 @@ impl Instruction for JumpInstructions {
                 (should_jump, displacement)
             }
             JumpInstructions::LoopWhileZeroOrEqual(AnyInstruction { destination, .. }) => {
-                let cx_value: i16 = store.register_store().read_value(Cx).into();
-                let cx_value = cx_value - 1;
+                let cx_value = store
+                    .register_store()
+                    .read_word_from_register(Cx)
+                    .wrapping_sub(1);
 
                 store
                     .register_store_mut()
-                    .write_word_to_register(Cx, ImmediateValue::SignedWord(cx_value).into());
+                    .write_word_to_register(Cx, cx_value);
 
                 let should_jump = cx_value != 0 && store.flag_register_store().get_flag(Zero);
                 let displacement = destination.to_immediate_value(true, store);
@@ -519,12 +291,14 @@ impl Instruction for JumpInstructions {
             JumpInstructions::LoopWhileNotZeroAndNotEqual(AnyInstruction {
                 destination, ..
             }) => {
-                let cx_value: i16 = store.register_store().read_value(Cx).into();
-                let cx_value = cx_value - 1;
+                let cx_value = store
+                    .register_store()
+                    .read_word_from_register(Cx)
+                    .wrapping_sub(1);
 
                 store
                     .register_store_mut()
-                    .write_word_to_register(Cx, ImmediateValue::SignedWord(cx_value).into());
+                    .write_word_to_register(Cx, cx_value);
 
                 let should_jump = cx_value != 0 && !store.flag_register_store().get_flag(Zero);
                 let displacement = destination.to_immediate_value(true, store);
@@ -537,19 +311,51 @@ impl Instruction for JumpInstructions {
 
                 (cx_value == 0, displacement)
             }
+            JumpInstructions::JumpDirectWithinSegment(AnyInstruction { destination, .. })
+            | JumpInstructions::JumpDirectWithinSegmentShort(AnyInstruction {
+                destination, ..
+            }) => (true, destination.to_immediate_value(true, store)),
+            JumpInstructions::JumpIndirectWithinSegment(AnyInstruction { destination, .. }) => {
+                let (target, clock_penalty) = destination.to_immediate_value(true, store);
+                set_instruction_pointer(reader, target.into());
+
+                return self.num_cycles() + if clock_penalty { 4 } else { 0 };
+            }
+            JumpInstructions::JumpDirectIntersegment(AnyInstruction { destination, .. }) => {
+                let Operand::FarPointer(segment, offset) = *destination else {
+                    panic!("Direct intersegment jump expects a far pointer")
+                };
+
+                store
+                    .segment_register_store_mut()
+                    .write_word_to_segment_register(SegmentRegister::Cs, segment);
+                set_instruction_pointer(reader, far_offset(offset));
+
+                return self.num_cycles();
+            }
+            JumpInstructions::JumpIndirectIntersegment(AnyInstruction { destination, .. }) => {
+                let Operand::Memory(address) = *destination else {
+                    panic!("Indirect intersegment jump expects a memory operand")
+                };
+
+                let ((segment, offset), clock_penalty) =
+                    store.read_far_pointer_from_effective_memory_address(address);
+
+                store
+                    .segment_register_store_mut()
+                    .write_word_to_segment_register(SegmentRegister::Cs, segment);
+                set_instruction_pointer(reader, offset);
+
+                return self.num_cycles() + if clock_penalty { 4 } else { 0 };
+            }
         };
 
+        // IP wraps around within the code segment, like a branch back from its start does.
         if should_jump {
             let displacement: i16 = displacement.into();
+            let ip = instruction_pointer(reader).wrapping_add_signed(displacement);
 
-            reader
-                .seek_relative(displacement as i64)
-                .unwrap_or_else(|_| {
-                    panic!(
-                        "Failed to jump {displacement} from {:?}",
-                        reader.stream_position()
-                    )
-                });
+            set_instruction_pointer(reader, ip);
         }
 
         self.num_cycles() + if clock_penalty { 4 } else { 0 }
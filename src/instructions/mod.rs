@@ -1,11 +1,30 @@
-mod add;
-pub mod arithmetic;
-mod compare;
+mod adjust;
+mod alu;
+mod bound;
+mod call;
 pub mod decode;
+mod decrement;
+mod divide;
+pub mod encode;
+pub mod encoding;
+pub mod generic;
+mod increment;
 pub mod instruction;
+mod interrupt;
+mod io;
 mod jump;
 mod mov;
+mod multiply;
+mod negate;
+mod not;
 pub mod operands;
-mod subtract;
+pub mod prefix;
+mod processor_control;
+mod ret;
+mod shift;
+mod stack;
+mod string;
+pub mod table;
+mod transfer;
 
 pub use instruction::*;
@@ -0,0 +1,176 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::{interpolate_cycles, EstimatedCycleCount};
+use crate::flag_register::FlagRegister;
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::{AnyInstruction, Execute};
+use crate::register::Register;
+use crate::store::Store;
+
+pub enum MultiplyInstructions {
+    Multiply(AnyInstruction),
+    SignedMultiply(AnyInstruction),
+    SignedMultiplyImmediate(AnyInstruction),
+}
+
+impl MultiplyInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            MultiplyInstructions::Multiply(instruction)
+            | MultiplyInstructions::SignedMultiply(instruction)
+            | MultiplyInstructions::SignedMultiplyImmediate(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            MultiplyInstructions::Multiply(instruction)
+            | MultiplyInstructions::SignedMultiply(instruction)
+            | MultiplyInstructions::SignedMultiplyImmediate(instruction) => instruction,
+        }
+    }
+
+    /// The 80186 form keeps only the low word of the product, in a register of its choosing.
+    fn execute_with_immediate(
+        instruction: &AnyInstruction,
+        store: &mut Store,
+        cycle_range: (u32, u32),
+    ) -> u32 {
+        let (multiplicand, clock_penalty) = instruction
+            .source
+            .expect("imul expects a source")
+            .to_immediate_value(true, store);
+        let multiplier: i16 = instruction
+            .immediate
+            .expect("imul expects an immediate")
+            .into();
+
+        let product = i32::from(i16::from(multiplicand)) * i32::from(multiplier);
+        let overflows = i16::try_from(product).is_err();
+
+        instruction.destination.write_value(
+            ImmediateValue::SignedWord(product as i16),
+            true,
+            store,
+        );
+
+        let flags = store.flag_register_store_mut();
+        flags.write_flag(FlagRegister::Carry, overflows);
+        flags.write_flag(FlagRegister::Overflow, overflows);
+
+        interpolate_cycles(cycle_range, multiplier.unsigned_abs().count_ones(), 16)
+            + if clock_penalty { 4 } else { 0 }
+    }
+}
+
+impl EstimatedCycleCount for MultiplyInstructions {
+    /// Without the operands the best guess is halfway through the documented range.
+    fn num_cycles(&self) -> u32 {
+        let (min, max) = self.cycle_range();
+
+        (min + max) / 2
+    }
+
+    fn cycle_range(&self) -> (u32, u32) {
+        let instruction = self.instruction();
+
+        // The 80186 form's timings already include computing the effective address.
+        if let MultiplyInstructions::SignedMultiplyImmediate(instruction) = self {
+            return match instruction.source {
+                Some(Operand::Register(_)) => (22, 25),
+                Some(Operand::Memory(_)) => (29, 32),
+                _ => panic!("Invalid multiply operation"),
+            };
+        }
+
+        let (min, max) = match (self, instruction.is_wide) {
+            (MultiplyInstructions::Multiply(_), false) => (70, 77),
+            (MultiplyInstructions::Multiply(_), true) => (118, 133),
+            (MultiplyInstructions::SignedMultiply(_), false) => (80, 98),
+            (MultiplyInstructions::SignedMultiply(_), true) => (128, 154),
+            (MultiplyInstructions::SignedMultiplyImmediate(_), _) => unreachable!(),
+        };
+
+        match instruction.destination {
+            Operand::Register(_) => (min, max),
+            Operand::Memory(ea) => (min + 6 + ea.num_cycles(), max + 6 + ea.num_cycles()),
+            _ => panic!("Invalid multiply operation"),
+        }
+    }
+}
+
+impl Execute for MultiplyInstructions {
+    /// The microcode adds once per set bit of the multiplier, so that is what places the
+    /// instruction within its range.
+    fn execute(&self, _reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        let instruction = self.instruction();
+        let is_wide = instruction.is_wide;
+
+        if let MultiplyInstructions::SignedMultiplyImmediate(instruction) = self {
+            return Self::execute_with_immediate(instruction, store, self.cycle_range());
+        }
+
+        let (multiplier, clock_penalty) =
+            instruction.destination.to_immediate_value(is_wide, store);
+        let multiplier: i16 = multiplier.into();
+
+        let registers = store.register_store_mut();
+
+        let (overflows, multiplier_bits) = match (self, is_wide) {
+            (MultiplyInstructions::Multiply(_), false) => {
+                let multiplier = multiplier as u8;
+                let product = u16::from(registers.read_byte_from_register(Register::Al))
+                    * u16::from(multiplier);
+
+                registers.write_word_to_register(Register::Ax, product);
+
+                (product > 0xFF, multiplier.count_ones())
+            }
+            (MultiplyInstructions::Multiply(_), true) => {
+                let multiplier = multiplier as u16;
+                let product = u32::from(registers.read_word_from_register(Register::Ax))
+                    * u32::from(multiplier);
+
+                registers.write_word_to_register(Register::Ax, product as u16);
+                registers.write_word_to_register(Register::Dx, (product >> 16) as u16);
+
+                (product > 0xFFFF, multiplier.count_ones())
+            }
+            (MultiplyInstructions::SignedMultiply(_), false) => {
+                let multiplier = multiplier as i8;
+                let product = i16::from(registers.read_byte_from_register(Register::Al) as i8)
+                    * i16::from(multiplier);
+
+                registers.write_word_to_register(Register::Ax, product as u16);
+
+                (
+                    i8::try_from(product).is_err(),
+                    multiplier.unsigned_abs().count_ones(),
+                )
+            }
+            (MultiplyInstructions::SignedMultiply(_), true) => {
+                let product = i32::from(registers.read_word_from_register(Register::Ax) as i16)
+                    * i32::from(multiplier);
+
+                registers.write_word_to_register(Register::Ax, product as u16);
+                registers.write_word_to_register(Register::Dx, (product >> 16) as u16);
+
+                (
+                    i16::try_from(product).is_err(),
+                    multiplier.unsigned_abs().count_ones(),
+                )
+            }
+            (MultiplyInstructions::SignedMultiplyImmediate(_), _) => unreachable!(),
+        };
+
+        // Only carry and overflow are defined, telling whether the upper half of the product is
+        // significant.
+        let flags = store.flag_register_store_mut();
+        flags.write_flag(FlagRegister::Carry, overflows);
+        flags.write_flag(FlagRegister::Overflow, overflows);
+
+        let num_bits = if is_wide { 16 } else { 8 };
+
+        interpolate_cycles(self.cycle_range(), multiplier_bits, num_bits)
+            + if clock_penalty { 4 } else { 0 }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::instructions::operands::Operand;
+use crate::instructions::{AnyInstruction, Execute};
+use crate::store::Store;
+
+pub struct NegateInstruction(pub AnyInstruction);
+
+impl EstimatedCycleCount for NegateInstruction {
+    fn num_cycles(&self) -> u32 {
+        match self.0.destination {
+            Operand::Register(_) => 3,
+            Operand::Memory(ea) => 16 + ea.num_cycles(),
+            _ => panic!("Invalid NEG operation"),
+        }
+    }
+}
+
+impl Execute for NegateInstruction {
+    fn execute(&self, _reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        let (value, clock_penalty) = self.0.destination.to_immediate_value(self.0.is_wide, store);
+
+        let op_result = -value;
+
+        store.flag_register_store_mut().set_flags_on_op(op_result);
+
+        self.0
+            .destination
+            .write_value(op_result.value(), self.0.is_wide, store);
+
+        self.num_cycles() + if clock_penalty { 4 } else { 0 }
+    }
+}
@@ -0,0 +1,30 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::instructions::operands::Operand;
+use crate::instructions::{AnyInstruction, Execute};
+use crate::store::Store;
+
+pub struct NotInstruction(pub AnyInstruction);
+
+impl EstimatedCycleCount for NotInstruction {
+    fn num_cycles(&self) -> u32 {
+        match self.0.destination {
+            Operand::Register(_) => 3,
+            Operand::Memory(ea) => 16 + ea.num_cycles(),
+            _ => panic!("Invalid NOT operation"),
+        }
+    }
+}
+
+impl Execute for NotInstruction {
+    fn execute(&self, _reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        let (value, clock_penalty) = self.0.destination.to_immediate_value(self.0.is_wide, store);
+
+        // Unlike the other logical operations, not leaves every flag untouched.
+        self.0
+            .destination
+            .write_value(!value, self.0.is_wide, store);
+
+        self.num_cycles() + if clock_penalty { 4 } else { 0 }
+    }
+}
@@ -1,18 +1,24 @@
+use crate::cursor::ByteCursor;
+use crate::error::DecodeResult;
+use crate::flag_register::{FlagMask, FlagRegister};
+use crate::instructions::prefix::Prefixes;
 use crate::memory::EffectiveAddress;
 use crate::mode::InstructionMode;
 use crate::register::Register;
 use crate::segment_register::SegmentRegister;
 use crate::store::Store;
-use crate::{BoxDynError, Byte, SignedByte, SignedWord, Wide};
+use crate::{BoxDynError, Byte, SignedByte, SignedWord, Wide, Word};
+use byteorder::{LittleEndian, ReadBytesExt};
 use std::fmt::{Display, Formatter};
-use std::fs::File;
-use std::io::BufReader;
-use std::ops::{Add, Sub};
+use std::ops::{Add, BitAnd, BitOr, BitXor, Neg, Not, Sub};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Copy, Clone, PartialEq)]
 pub enum ImmediateValue {
     SignedByte(i8),
     SignedWord(i16),
+    /// Only ever decoded, under an operand size prefix.
+    SignedDoubleWord(i32),
 }
 
 impl From<i16> for ImmediateValue {
@@ -32,6 +38,9 @@ impl From<ImmediateValue> for i16 {
         match value {
             ImmediateValue::SignedByte(value) => value as i16,
             ImmediateValue::SignedWord(value) => value,
+            ImmediateValue::SignedDoubleWord(_) => {
+                panic!("32-bit operands are only decoded, not simulated")
+            }
         }
     }
 }
@@ -42,7 +51,9 @@ impl TryFrom<ImmediateValue> for i8 {
     fn try_from(value: ImmediateValue) -> Result<Self, Self::Error> {
         match value {
             ImmediateValue::SignedByte(value) => Ok(value),
-            ImmediateValue::SignedWord(_) => Err("Cannot read word as byte".into()),
+            ImmediateValue::SignedWord(_) | ImmediateValue::SignedDoubleWord(_) => {
+                Err("Cannot read word as byte".into())
+            }
         }
     }
 }
@@ -52,6 +63,19 @@ impl From<ImmediateValue> for u16 {
         match value {
             ImmediateValue::SignedByte(value) => u16::from_le_bytes((value as i16).to_le_bytes()),
             ImmediateValue::SignedWord(value) => u16::from_le_bytes((value).to_le_bytes()),
+            ImmediateValue::SignedDoubleWord(_) => {
+                panic!("32-bit operands are only decoded, not simulated")
+            }
+        }
+    }
+}
+
+impl From<ImmediateValue> for i32 {
+    fn from(value: ImmediateValue) -> Self {
+        match value {
+            ImmediateValue::SignedByte(value) => i32::from(value),
+            ImmediateValue::SignedWord(value) => i32::from(value),
+            ImmediateValue::SignedDoubleWord(value) => value,
         }
     }
 }
@@ -62,7 +86,9 @@ impl TryFrom<ImmediateValue> for u8 {
     fn try_from(value: ImmediateValue) -> Result<Self, Self::Error> {
         match value {
             ImmediateValue::SignedByte(value) => Ok(u8::from_le_bytes(value.to_le_bytes())),
-            ImmediateValue::SignedWord(_) => Err("Cannot read word as byte".into()),
+            ImmediateValue::SignedWord(_) | ImmediateValue::SignedDoubleWord(_) => {
+                Err("Cannot read word as byte".into())
+            }
         }
     }
 }
@@ -72,6 +98,9 @@ impl From<ArithmeticResult> for i16 {
         match value {
             ImmediateValue::SignedByte(value) => value as i16,
             ImmediateValue::SignedWord(value) => value,
+            ImmediateValue::SignedDoubleWord(_) => {
+                panic!("32-bit operands are only decoded, not simulated")
+            }
         }
     }
 }
@@ -82,7 +111,9 @@ impl TryFrom<ArithmeticResult> for i8 {
     fn try_from(ArithmeticResult { value, .. }: ArithmeticResult) -> Result<Self, Self::Error> {
         match value {
             ImmediateValue::SignedByte(value) => Ok(value),
-            ImmediateValue::SignedWord(_) => Err("Cannot read word as byte".into()),
+            ImmediateValue::SignedWord(_) | ImmediateValue::SignedDoubleWord(_) => {
+                Err("Cannot read word as byte".into())
+            }
         }
     }
 }
@@ -92,6 +123,9 @@ impl From<ArithmeticResult> for u16 {
         match value {
             ImmediateValue::SignedByte(value) => u16::from_le_bytes((value as i16).to_le_bytes()),
             ImmediateValue::SignedWord(value) => u16::from_le_bytes((value).to_le_bytes()),
+            ImmediateValue::SignedDoubleWord(_) => {
+                panic!("32-bit operands are only decoded, not simulated")
+            }
         }
     }
 }
@@ -102,7 +136,9 @@ impl TryFrom<ArithmeticResult> for u8 {
     fn try_from(ArithmeticResult { value, .. }: ArithmeticResult) -> Result<Self, Self::Error> {
         match value {
             ImmediateValue::SignedByte(value) => Ok(u8::from_le_bytes(value.to_le_bytes())),
-            ImmediateValue::SignedWord(_) => Err("Cannot read word as byte".into()),
+            ImmediateValue::SignedWord(_) | ImmediateValue::SignedDoubleWord(_) => {
+                Err("Cannot read word as byte".into())
+            }
         }
     }
 }
@@ -110,6 +146,7 @@ impl TryFrom<ArithmeticResult> for u8 {
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub struct ArithmeticResult<Value = ImmediateValue> {
     value: Value,
+    pub affected_flags: FlagMask,
     pub carry: bool,
     pub auxiliary_carry: bool,
     pub overflow: bool,
@@ -121,6 +158,14 @@ impl<Value> ArithmeticResult<Value> {
     pub fn value(self) -> Value {
         self.value
     }
+
+    /// Leaves `flag` as it was when the result is applied to the flags register.
+    pub fn preserving(self, flag: FlagRegister) -> Self {
+        Self {
+            affected_flags: self.affected_flags.without(flag),
+            ..self
+        }
+    }
 }
 
 fn compute_carry_on_addition(lhs: i16, rhs: i16, value: i16) -> (bool, bool) {
@@ -153,27 +198,7 @@ impl Add for ImmediateValue {
     type Output = ArithmeticResult;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let lhs = match self {
-            ImmediateValue::SignedByte(value) => value as i16,
-            ImmediateValue::SignedWord(value) => value,
-        };
-
-        let rhs = match rhs {
-            ImmediateValue::SignedByte(value) => value as i16,
-            ImmediateValue::SignedWord(value) => value,
-        };
-
-        let (value, overflow) = lhs.overflowing_add(rhs);
-        let (carry, auxiliary_carry) = compute_carry_on_addition(lhs, rhs, value);
-
-        ArithmeticResult {
-            value: ImmediateValue::SignedWord(value),
-            zero: value == 0,
-            overflow,
-            carry,
-            auxiliary_carry,
-            sign: value.is_negative(),
-        }
+        self.add_with_carry(rhs, false)
     }
 }
 
@@ -181,27 +206,7 @@ impl Sub for ImmediateValue {
     type Output = ArithmeticResult;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        let lhs = match self {
-            ImmediateValue::SignedByte(value) => value as i16,
-            ImmediateValue::SignedWord(value) => value,
-        };
-
-        let rhs = match rhs {
-            ImmediateValue::SignedByte(value) => value as i16,
-            ImmediateValue::SignedWord(value) => value,
-        };
-
-        let (value, overflow) = lhs.overflowing_sub(rhs);
-        let (carry, auxiliary_carry) = compute_borrow_on_subtraction(lhs, rhs, value);
-
-        ArithmeticResult {
-            value: ImmediateValue::SignedWord(value),
-            zero: value == 0,
-            overflow,
-            carry,
-            auxiliary_carry,
-            sign: value.is_negative(),
-        }
+        self.subtract_with_borrow(rhs, false)
     }
 }
 
@@ -212,6 +217,9 @@ impl Add<SignedByte> for ImmediateValue {
         let lhs = match self {
             ImmediateValue::SignedByte(value) => value as i16,
             ImmediateValue::SignedWord(value) => value,
+            ImmediateValue::SignedDoubleWord(_) => {
+                panic!("32-bit operands are only decoded, not simulated")
+            }
         };
 
         let (value, overflow) = lhs.overflowing_add(rhs as i16);
@@ -224,6 +232,7 @@ impl Add<SignedByte> for ImmediateValue {
             carry,
             auxiliary_carry,
             sign: value.is_negative(),
+            affected_flags: FlagMask::ARITHMETIC,
         }
     }
 }
@@ -235,6 +244,9 @@ impl Sub<SignedByte> for ImmediateValue {
         let lhs = match self {
             ImmediateValue::SignedByte(value) => value as i16,
             ImmediateValue::SignedWord(value) => value,
+            ImmediateValue::SignedDoubleWord(_) => {
+                panic!("32-bit operands are only decoded, not simulated")
+            }
         };
 
         let (value, overflow) = lhs.overflowing_sub(rhs as i16);
@@ -247,6 +259,7 @@ impl Sub<SignedByte> for ImmediateValue {
             carry,
             auxiliary_carry,
             sign: value.is_negative(),
+            affected_flags: FlagMask::ARITHMETIC,
         }
     }
 }
@@ -258,6 +271,9 @@ impl Add<SignedWord> for ImmediateValue {
         let lhs = match self {
             ImmediateValue::SignedByte(value) => value as i16,
             ImmediateValue::SignedWord(value) => value,
+            ImmediateValue::SignedDoubleWord(_) => {
+                panic!("32-bit operands are only decoded, not simulated")
+            }
         };
 
         let (value, overflow) = lhs.overflowing_add(rhs);
@@ -270,6 +286,7 @@ impl Add<SignedWord> for ImmediateValue {
             carry,
             auxiliary_carry,
             sign: value.is_negative(),
+            affected_flags: FlagMask::ARITHMETIC,
         }
     }
 }
@@ -281,6 +298,9 @@ impl Sub<SignedWord> for ImmediateValue {
         let lhs = match self {
             ImmediateValue::SignedByte(value) => value as i16,
             ImmediateValue::SignedWord(value) => value,
+            ImmediateValue::SignedDoubleWord(_) => {
+                panic!("32-bit operands are only decoded, not simulated")
+            }
         };
 
         let (value, overflow) = lhs.overflowing_sub(rhs);
@@ -293,6 +313,187 @@ impl Sub<SignedWord> for ImmediateValue {
             carry,
             auxiliary_carry,
             sign: value.is_negative(),
+            affected_flags: FlagMask::ARITHMETIC,
+        }
+    }
+}
+
+impl ImmediateValue {
+    /// Reads a word immediate, or a double word under an operand size prefix.
+    pub fn read_wide(reader: &mut impl ByteCursor, prefixes: Prefixes) -> DecodeResult<Self> {
+        Ok(if prefixes.operand_size_override {
+            ImmediateValue::SignedDoubleWord(reader.read_i32::<LittleEndian>()?)
+        } else {
+            ImmediateValue::SignedWord(reader.read_i16::<LittleEndian>()?)
+        })
+    }
+
+    /// Sign extends a byte immediate to the width of a wide operation.
+    pub fn extend_byte(value: SignedByte, prefixes: Prefixes) -> Self {
+        if prefixes.operand_size_override {
+            ImmediateValue::SignedDoubleWord(i32::from(value))
+        } else {
+            ImmediateValue::SignedWord(i16::from(value))
+        }
+    }
+
+    /// Adds `carry` on top of `rhs`, so that wider additions can be chained through CF.
+    pub fn add_with_carry(self, rhs: Self, carry: bool) -> ArithmeticResult {
+        self.arithmetic_result(rhs, false, carry)
+    }
+
+    /// Subtracts `borrow` on top of `rhs`, so that wider subtractions can be chained through CF.
+    pub fn subtract_with_borrow(self, rhs: Self, borrow: bool) -> ArithmeticResult {
+        self.arithmetic_result(rhs, true, borrow)
+    }
+
+    /// Adds or subtracts at the width of the operands, a byte only if both of them are bytes, so
+    /// that carries out of bit 7 and bit 3 end up in CF and AF like they do on the 8086. The
+    /// incoming carry takes part in every flag, as if it were part of `rhs`.
+    fn arithmetic_result(self, rhs: Self, is_subtraction: bool, carry: bool) -> ArithmeticResult {
+        let (mask, sign_bit): (u32, u32) = match (self, rhs) {
+            (ImmediateValue::SignedByte(_), ImmediateValue::SignedByte(_)) => (0xFF, 0x80),
+            _ => (0xFFFF, 0x8000),
+        };
+
+        let lhs = u32::from(u16::from(self)) & mask;
+        let rhs = u32::from(u16::from(rhs)) & mask;
+
+        let carry = u32::from(carry);
+
+        let (value, carry, auxiliary_carry, overflow) = if is_subtraction {
+            let value = lhs.wrapping_sub(rhs).wrapping_sub(carry) & mask;
+
+            (
+                value,
+                lhs < rhs + carry,
+                (lhs & 0xF) < (rhs & 0xF) + carry,
+                (lhs ^ rhs) & (lhs ^ value) & sign_bit != 0,
+            )
+        } else {
+            let value = (lhs + rhs + carry) & mask;
+
+            (
+                value,
+                lhs + rhs + carry > mask,
+                (lhs & 0xF) + (rhs & 0xF) + carry > 0xF,
+                !(lhs ^ rhs) & (lhs ^ value) & sign_bit != 0,
+            )
+        };
+
+        ArithmeticResult {
+            value: if mask == 0xFF {
+                ImmediateValue::SignedByte(value as u8 as i8)
+            } else {
+                ImmediateValue::SignedWord(value as u16 as i16)
+            },
+            zero: value == 0,
+            overflow,
+            carry,
+            auxiliary_carry,
+            sign: value & sign_bit != 0,
+            affected_flags: FlagMask::ARITHMETIC,
+        }
+    }
+
+    /// Logical operations keep the width of their operands, and always clear carry and
+    /// overflow.
+    fn logical_result(self, rhs: Self, op: fn(i16, i16) -> i16) -> ArithmeticResult {
+        let value = match (self, rhs) {
+            (ImmediateValue::SignedByte(lhs), ImmediateValue::SignedByte(rhs)) => {
+                ImmediateValue::SignedByte(op(lhs as i16, rhs as i16) as i8)
+            }
+            (lhs, rhs) => ImmediateValue::SignedWord(op(lhs.into(), rhs.into())),
+        };
+
+        let result: i16 = value.into();
+
+        ArithmeticResult {
+            value,
+            zero: result == 0,
+            overflow: false,
+            carry: false,
+            auxiliary_carry: false,
+            sign: result.is_negative(),
+            affected_flags: FlagMask::ARITHMETIC,
+        }
+    }
+}
+
+impl BitAnd for ImmediateValue {
+    type Output = ArithmeticResult;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.logical_result(rhs, |lhs, rhs| lhs & rhs)
+    }
+}
+
+impl BitOr for ImmediateValue {
+    type Output = ArithmeticResult;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.logical_result(rhs, |lhs, rhs| lhs | rhs)
+    }
+}
+
+impl BitXor for ImmediateValue {
+    type Output = ArithmeticResult;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.logical_result(rhs, |lhs, rhs| lhs ^ rhs)
+    }
+}
+
+impl Not for ImmediateValue {
+    type Output = ImmediateValue;
+
+    fn not(self) -> Self::Output {
+        match self {
+            ImmediateValue::SignedByte(value) => ImmediateValue::SignedByte(!value),
+            ImmediateValue::SignedWord(value) => ImmediateValue::SignedWord(!value),
+            ImmediateValue::SignedDoubleWord(_) => {
+                panic!("32-bit operands are only decoded, not simulated")
+            }
+        }
+    }
+}
+
+impl Neg for ImmediateValue {
+    type Output = ArithmeticResult;
+
+    /// Subtracts the operand from zero at its own width, so that the borrow out of bit 7 sets the
+    /// carry for bytes instead of being sign extended into a word.
+    fn neg(self) -> Self::Output {
+        match self {
+            ImmediateValue::SignedByte(operand) => {
+                let value = operand.wrapping_neg();
+
+                ArithmeticResult {
+                    value: ImmediateValue::SignedByte(value),
+                    zero: value == 0,
+                    overflow: operand == i8::MIN,
+                    carry: operand != 0,
+                    auxiliary_carry: operand & 0xF != 0,
+                    sign: value.is_negative(),
+                    affected_flags: FlagMask::ARITHMETIC,
+                }
+            }
+            ImmediateValue::SignedWord(operand) => {
+                let value = operand.wrapping_neg();
+
+                ArithmeticResult {
+                    value: ImmediateValue::SignedWord(value),
+                    zero: value == 0,
+                    overflow: operand == i16::MIN,
+                    carry: operand != 0,
+                    auxiliary_carry: operand & 0xF != 0,
+                    sign: value.is_negative(),
+                    affected_flags: FlagMask::ARITHMETIC,
+                }
+            }
+            ImmediateValue::SignedDoubleWord(_) => {
+                panic!("32-bit operands are only decoded, not simulated")
+            }
         }
     }
 }
@@ -302,6 +503,7 @@ impl Display for ImmediateValue {
         match self {
             ImmediateValue::SignedByte(value) => Display::fmt(value, f),
             ImmediateValue::SignedWord(value) => Display::fmt(value, f),
+            ImmediateValue::SignedDoubleWord(value) => Display::fmt(value, f),
         }
     }
 }
@@ -314,6 +516,8 @@ pub enum Operand {
     SegmentRegister(SegmentRegister),
     Memory(EffectiveAddress),
     Immediate(ImmediateValue),
+    /// A segment and an offset, which is 32 bits wide under an operand size prefix.
+    FarPointer(Word, u32),
 }
 
 impl Display for Operand {
@@ -325,29 +529,44 @@ impl Display for Operand {
             Operand::Memory(memory) => memory.fmt(f),
             Operand::Immediate(immediate) => immediate.fmt(f),
             Operand::SegmentRegister(register) => register.fmt(f),
+            Operand::FarPointer(segment, offset) => f.write_fmt(format_args!("{segment}:{offset}")),
         }
     }
 }
 
 impl Operand {
     pub fn read(
-        reader: &mut BufReader<File>,
+        reader: &mut impl ByteCursor,
         mode: InstructionMode,
         target_specifier_byte: Byte,
         is_wide: Wide,
-    ) -> Self {
+        prefixes: Prefixes,
+    ) -> DecodeResult<Self> {
         let mem_bytes = 0b00_000_111 & target_specifier_byte;
 
-        if let InstructionMode::Register = mode {
+        Ok(if let InstructionMode::Register = mode {
             let mut mem_bytes = mem_bytes << 1;
 
             if is_wide {
                 mem_bytes += 1;
             }
 
-            Operand::Register(Register::from(mem_bytes))
+            Operand::Register(Register::from(mem_bytes).sized(is_wide, prefixes))
+        } else if prefixes.address_size_override {
+            Operand::Memory(EffectiveAddress::read_extended(reader, mode, mem_bytes)?)
+        } else {
+            Operand::Memory(EffectiveAddress::read(reader, mode, mem_bytes)?)
+        })
+    }
+
+    /// The accumulator at the width of the operation.
+    pub fn accumulator(is_wide: Wide, prefixes: Prefixes) -> Self {
+        if prefixes.is_double_word(is_wide) {
+            Operand::Register(Register::Eax)
+        } else if is_wide {
+            Operand::AccumulatorWide
         } else {
-            Operand::Memory(EffectiveAddress::read(reader, mode, mem_bytes))
+            Operand::Accumulator
         }
     }
 
@@ -388,12 +607,11 @@ impl Operand {
                     )
                 }
             }
-            Operand::Memory(address) => store.memory_store().read_memory_from_effective_address(
-                address,
-                is_wide_op,
-                store.register_store(),
-            ),
+            Operand::Memory(address) => {
+                store.read_from_effective_memory_address(address, is_wide_op)
+            }
             Operand::Immediate(immediate_value) => (immediate_value, false),
+            Operand::FarPointer(..) => panic!("Cannot read a far pointer as a single value"),
             Operand::SegmentRegister(register) => {
                 if is_wide_op {
                     (
@@ -432,6 +650,9 @@ impl Operand {
                 ImmediateValue::SignedWord(_) => {
                     panic!("Cannot write word to Al")
                 }
+                ImmediateValue::SignedDoubleWord(_) => {
+                    panic!("32-bit operands are only decoded, not simulated")
+                }
             },
             Operand::AccumulatorWide => match value {
                 ImmediateValue::SignedByte(value) => {
@@ -443,6 +664,9 @@ impl Operand {
                 ImmediateValue::SignedWord(value) => store
                     .register_store_mut()
                     .write_word_to_register(Register::Ax, u16::from_le_bytes(value.to_le_bytes())),
+                ImmediateValue::SignedDoubleWord(_) => {
+                    panic!("32-bit operands are only decoded, not simulated")
+                }
             },
             Operand::Register(register) => match value {
                 ImmediateValue::SignedByte(value) => {
@@ -461,22 +685,18 @@ impl Operand {
                 ImmediateValue::SignedWord(value) => store
                     .register_store_mut()
                     .write_word_to_register(register, u16::from_le_bytes(value.to_le_bytes())),
+                ImmediateValue::SignedDoubleWord(_) => {
+                    panic!("32-bit operands are only decoded, not simulated")
+                }
             },
             Operand::Memory(address) => {
                 store.write_to_effective_memory_address(address, is_wide_op, value)
             }
             Operand::Immediate(_) => panic!("Cannot move a value to immediate"),
-            Operand::SegmentRegister(register) => {
-                if is_wide_op {
-                    store
-                        .segment_register_store_mut()
-                        .write_word_to_segment_register(register, value.into())
-                } else {
-                    store
-                        .segment_register_store_mut()
-                        .write_word_to_segment_register(register, value.try_into().unwrap())
-                }
-            }
+            Operand::FarPointer(..) => panic!("Cannot move a value to a far pointer"),
+            Operand::SegmentRegister(register) => store
+                .segment_register_store_mut()
+                .write_word_to_segment_register(register, value.into()),
         }
     }
 }
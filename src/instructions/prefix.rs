@@ -0,0 +1,85 @@
+use crate::cpu::CpuModel;
+use crate::prelude::*;
+use crate::segment_register::SegmentRegister;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Repeat {
+    WhileZero,
+    WhileNotZero,
+}
+
+#[derive(Copy, Clone, Default, PartialEq)]
+pub struct Prefixes {
+    pub lock: bool,
+    pub repeat: Option<Repeat>,
+    pub segment_override: Option<SegmentRegister>,
+    /// Set by 0x66, which turns word operands into double words.
+    pub operand_size_override: bool,
+    /// Set by 0x67, which switches memory operands to the 32-bit addressing forms.
+    pub address_size_override: bool,
+}
+
+impl Prefixes {
+    pub fn is_prefix(value: Byte, model: CpuModel) -> bool {
+        bit_match!(value, (0, 0, 1, _, _, 1, 1, 0))
+            || bit_match!(value, (1, 1, 1, 1, 0, 0, 0, 0))
+            || bit_match!(value, (1, 1, 1, 1, 0, 0, 1, _))
+            || (model >= CpuModel::Intel80386 && bit_match!(value, (0, 1, 1, 0, 0, 1, 1, _)))
+    }
+
+    pub fn add(&mut self, value: Byte) {
+        match value {
+            value if bit_match!(value, (0, 0, 1, _, _, 1, 1, 0)) => {
+                self.segment_override = Some(SegmentRegister::from((0b00_011_000 & value) >> 3));
+            }
+            value if bit_match!(value, (1, 1, 1, 1, 0, 0, 0, 0)) => self.lock = true,
+            value if bit_match!(value, (1, 1, 1, 1, 0, 0, 1, 0)) => {
+                self.repeat = Some(Repeat::WhileNotZero);
+            }
+            value if bit_match!(value, (1, 1, 1, 1, 0, 0, 1, 1)) => {
+                self.repeat = Some(Repeat::WhileZero);
+            }
+            value if bit_match!(value, (0, 1, 1, 0, 0, 1, 1, 0)) => {
+                self.operand_size_override = true;
+            }
+            value if bit_match!(value, (0, 1, 1, 0, 0, 1, 1, 1)) => {
+                self.address_size_override = true;
+            }
+            value => panic!("Not a prefix: {value}"),
+        }
+    }
+
+    /// The bytes that set these prefixes, in the order nasm writes them.
+    pub fn bytes(&self) -> Vec<Byte> {
+        let mut bytes = Vec::new();
+
+        if self.lock {
+            bytes.push(0xf0);
+        }
+
+        match self.repeat {
+            Some(Repeat::WhileNotZero) => bytes.push(0xf2),
+            Some(Repeat::WhileZero) => bytes.push(0xf3),
+            None => {}
+        }
+
+        if let Some(segment) = self.segment_override {
+            bytes.push(0x26 | segment.code() << 3);
+        }
+
+        if self.operand_size_override {
+            bytes.push(0x66);
+        }
+
+        if self.address_size_override {
+            bytes.push(0x67);
+        }
+
+        bytes
+    }
+
+    /// Whether word operands are double words instead.
+    pub fn is_double_word(&self, is_wide: Wide) -> bool {
+        is_wide && self.operand_size_override
+    }
+}
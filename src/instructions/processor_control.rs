@@ -0,0 +1,104 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::flag_register::FlagRegister;
+use crate::instructions::operands::Operand;
+use crate::instructions::{AnyInstruction, Execute};
+use crate::store::{StopReason, Store};
+
+pub enum ProcessorControlInstructions {
+    ClearCarry(AnyInstruction),
+    ComplementCarry(AnyInstruction),
+    SetCarry(AnyInstruction),
+    ClearDirection(AnyInstruction),
+    SetDirection(AnyInstruction),
+    ClearInterrupt(AnyInstruction),
+    SetInterrupt(AnyInstruction),
+    Halt(AnyInstruction),
+    Wait(AnyInstruction),
+    Escape(AnyInstruction),
+    NoOperation(AnyInstruction),
+}
+
+impl ProcessorControlInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            ProcessorControlInstructions::ClearCarry(instruction)
+            | ProcessorControlInstructions::ComplementCarry(instruction)
+            | ProcessorControlInstructions::SetCarry(instruction)
+            | ProcessorControlInstructions::ClearDirection(instruction)
+            | ProcessorControlInstructions::SetDirection(instruction)
+            | ProcessorControlInstructions::ClearInterrupt(instruction)
+            | ProcessorControlInstructions::SetInterrupt(instruction)
+            | ProcessorControlInstructions::Halt(instruction)
+            | ProcessorControlInstructions::Wait(instruction)
+            | ProcessorControlInstructions::Escape(instruction)
+            | ProcessorControlInstructions::NoOperation(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            ProcessorControlInstructions::ClearCarry(instruction)
+            | ProcessorControlInstructions::ComplementCarry(instruction)
+            | ProcessorControlInstructions::SetCarry(instruction)
+            | ProcessorControlInstructions::ClearDirection(instruction)
+            | ProcessorControlInstructions::SetDirection(instruction)
+            | ProcessorControlInstructions::ClearInterrupt(instruction)
+            | ProcessorControlInstructions::SetInterrupt(instruction)
+            | ProcessorControlInstructions::Halt(instruction)
+            | ProcessorControlInstructions::Wait(instruction)
+            | ProcessorControlInstructions::Escape(instruction)
+            | ProcessorControlInstructions::NoOperation(instruction) => instruction,
+        }
+    }
+}
+
+impl EstimatedCycleCount for ProcessorControlInstructions {
+    fn num_cycles(&self) -> u32 {
+        match self {
+            ProcessorControlInstructions::ClearCarry(_)
+            | ProcessorControlInstructions::ComplementCarry(_)
+            | ProcessorControlInstructions::SetCarry(_)
+            | ProcessorControlInstructions::ClearDirection(_)
+            | ProcessorControlInstructions::SetDirection(_)
+            | ProcessorControlInstructions::ClearInterrupt(_)
+            | ProcessorControlInstructions::SetInterrupt(_)
+            | ProcessorControlInstructions::Halt(_) => 2,
+            ProcessorControlInstructions::Wait(_)
+            | ProcessorControlInstructions::NoOperation(_) => 3,
+            ProcessorControlInstructions::Escape(instruction) => match instruction.destination {
+                Operand::Memory(ea) => 8 + ea.num_cycles(),
+                _ => 2,
+            },
+        }
+    }
+}
+
+impl Execute for ProcessorControlInstructions {
+    fn execute(&self, _reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        use FlagRegister::*;
+
+        let flags = store.flag_register_store_mut();
+
+        match self {
+            ProcessorControlInstructions::ClearCarry(_) => flags.unset_flag(Carry),
+            ProcessorControlInstructions::ComplementCarry(_) => {
+                let carry = flags.get_flag(Carry);
+                flags.write_flag(Carry, !carry);
+            }
+            ProcessorControlInstructions::SetCarry(_) => flags.set_flag(Carry),
+            ProcessorControlInstructions::ClearDirection(_) => flags.unset_flag(Direction),
+            ProcessorControlInstructions::SetDirection(_) => flags.set_flag(Direction),
+            ProcessorControlInstructions::ClearInterrupt(_) => flags.unset_flag(Interrupt),
+            ProcessorControlInstructions::SetInterrupt(_) => flags.set_flag(Interrupt),
+            ProcessorControlInstructions::Halt(_) => store.stop(StopReason::Halted),
+            // There is no coprocessor attached, so nothing is ever busy and nothing picks up
+            // escaped opcodes.
+            ProcessorControlInstructions::Wait(_)
+            | ProcessorControlInstructions::Escape(_)
+            | ProcessorControlInstructions::NoOperation(_) => {}
+        }
+
+        self.num_cycles()
+    }
+}
@@ -0,0 +1,89 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::instructions::{set_instruction_pointer, AnyInstruction, Execute};
+use crate::register::Register;
+use crate::segment_register::SegmentRegister;
+use crate::store::{StopReason, Store};
+
+pub enum ReturnInstructions {
+    WithinSegment(AnyInstruction),
+    WithinSegmentAddingImmediateToSp(AnyInstruction),
+    Intersegment(AnyInstruction),
+    IntersegmentAddingImmediateToSp(AnyInstruction),
+}
+
+impl ReturnInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            ReturnInstructions::WithinSegment(instruction)
+            | ReturnInstructions::WithinSegmentAddingImmediateToSp(instruction)
+            | ReturnInstructions::Intersegment(instruction)
+            | ReturnInstructions::IntersegmentAddingImmediateToSp(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            ReturnInstructions::WithinSegment(instruction)
+            | ReturnInstructions::WithinSegmentAddingImmediateToSp(instruction)
+            | ReturnInstructions::Intersegment(instruction)
+            | ReturnInstructions::IntersegmentAddingImmediateToSp(instruction) => instruction,
+        }
+    }
+}
+
+impl EstimatedCycleCount for ReturnInstructions {
+    fn num_cycles(&self) -> u32 {
+        match self {
+            ReturnInstructions::WithinSegment(_) => 8,
+            ReturnInstructions::WithinSegmentAddingImmediateToSp(_) => 12,
+            ReturnInstructions::Intersegment(_) => 18,
+            ReturnInstructions::IntersegmentAddingImmediateToSp(_) => 17,
+        }
+    }
+}
+
+impl Execute for ReturnInstructions {
+    fn execute(&self, reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        if store.is_outermost_return() {
+            store.stop(StopReason::Returned);
+
+            return self.num_cycles();
+        }
+
+        let (ip, mut clock_penalty) = store.pop_word();
+
+        if let ReturnInstructions::Intersegment(_)
+        | ReturnInstructions::IntersegmentAddingImmediateToSp(_) = self
+        {
+            let (cs, cs_clock_penalty) = store.pop_word();
+            clock_penalty |= cs_clock_penalty;
+
+            store
+                .segment_register_store_mut()
+                .write_word_to_segment_register(SegmentRegister::Cs, cs);
+        }
+
+        if let ReturnInstructions::WithinSegmentAddingImmediateToSp(AnyInstruction {
+            destination,
+            ..
+        })
+        | ReturnInstructions::IntersegmentAddingImmediateToSp(AnyInstruction {
+            destination,
+            ..
+        }) = self
+        {
+            let (bytes_to_release, _) = destination.to_immediate_value(true, store);
+            let bytes_to_release: u16 = bytes_to_release.into();
+            let sp = store.register_store().read_word_from_register(Register::Sp);
+
+            store
+                .register_store_mut()
+                .write_word_to_register(Register::Sp, sp.wrapping_add(bytes_to_release));
+        }
+
+        set_instruction_pointer(reader, ip);
+
+        self.num_cycles() + if clock_penalty { 4 } else { 0 }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::cpu::CpuModel;
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::flag_register::FlagRegister;
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::{AnyInstruction, Execute};
+use crate::store::Store;
+
+pub enum ShiftInstructions {
+    ShiftLeft(AnyInstruction),
+    ShiftRight(AnyInstruction),
+    ShiftArithmeticRight(AnyInstruction),
+    RotateLeft(AnyInstruction),
+    RotateRight(AnyInstruction),
+    RotateThroughCarryLeft(AnyInstruction),
+    RotateThroughCarryRight(AnyInstruction),
+}
+
+impl ShiftInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            ShiftInstructions::ShiftLeft(instruction)
+            | ShiftInstructions::ShiftRight(instruction)
+            | ShiftInstructions::ShiftArithmeticRight(instruction)
+            | ShiftInstructions::RotateLeft(instruction)
+            | ShiftInstructions::RotateRight(instruction)
+            | ShiftInstructions::RotateThroughCarryLeft(instruction)
+            | ShiftInstructions::RotateThroughCarryRight(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            ShiftInstructions::ShiftLeft(instruction)
+            | ShiftInstructions::ShiftRight(instruction)
+            | ShiftInstructions::ShiftArithmeticRight(instruction)
+            | ShiftInstructions::RotateLeft(instruction)
+            | ShiftInstructions::RotateRight(instruction)
+            | ShiftInstructions::RotateThroughCarryLeft(instruction)
+            | ShiftInstructions::RotateThroughCarryRight(instruction) => instruction,
+        }
+    }
+}
+
+impl EstimatedCycleCount for ShiftInstructions {
+    fn num_cycles(&self) -> u32 {
+        use Operand::*;
+
+        let instruction = self.instruction();
+
+        // Shifts by CL additionally cost 4 cycles per bit, which is only known when executing.
+        match (instruction.destination, instruction.source.unwrap()) {
+            (Register(_), Immediate(ImmediateValue::SignedByte(_))) => 2,
+            (Memory(ea), Immediate(ImmediateValue::SignedByte(_))) => 15 + ea.num_cycles(),
+            (Register(_), Immediate(ImmediateValue::SignedWord(count))) => 5 + count as u32,
+            (Memory(_), Immediate(ImmediateValue::SignedWord(count))) => 17 + count as u32,
+            (Register(_), Register(_)) => 8,
+            (Memory(ea), Register(_)) => 20 + ea.num_cycles(),
+            _ => panic!("Invalid shift operation"),
+        }
+    }
+}
+
+impl Execute for ShiftInstructions {
+    fn execute(&self, _reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        use ShiftInstructions::*;
+
+        let instruction = self.instruction();
+        let is_wide = instruction.is_wide;
+
+        let (count, _) = instruction
+            .source
+            .expect("shift operation expects a count")
+            .to_immediate_value(false, store);
+        let mut count = u32::from(u16::from(count) & 0xFF);
+
+        // The 80186 and later only look at the low five bits of the count.
+        if store.cpu_model() >= CpuModel::Intel80186 {
+            count &= 0b1_1111;
+        }
+
+        let (value, clock_penalty) = instruction.destination.to_immediate_value(is_wide, store);
+
+        let bits = if is_wide { 16 } else { 8 };
+        let sign_bit = 1u32 << (bits - 1);
+        let mask = (1u32 << bits) - 1;
+
+        let mut result = u32::from(i16::from(value) as u16) & mask;
+        let mut carry = store.flag_register_store().get_flag(FlagRegister::Carry);
+
+        for _ in 0..count {
+            match self {
+                ShiftLeft(_) => {
+                    carry = result & sign_bit != 0;
+                    result = (result << 1) & mask;
+                }
+                ShiftRight(_) => {
+                    carry = result & 1 != 0;
+                    result >>= 1;
+                }
+                ShiftArithmeticRight(_) => {
+                    carry = result & 1 != 0;
+                    result = (result >> 1) | (result & sign_bit);
+                }
+                RotateLeft(_) => {
+                    carry = result & sign_bit != 0;
+                    result = ((result << 1) & mask) | u32::from(carry);
+                }
+                RotateRight(_) => {
+                    carry = result & 1 != 0;
+                    result = (result >> 1) | if carry { sign_bit } else { 0 };
+                }
+                RotateThroughCarryLeft(_) => {
+                    let carry_in = u32::from(carry);
+                    carry = result & sign_bit != 0;
+                    result = ((result << 1) & mask) | carry_in;
+                }
+                RotateThroughCarryRight(_) => {
+                    let carry_in = if carry { sign_bit } else { 0 };
+                    carry = result & 1 != 0;
+                    result = (result >> 1) | carry_in;
+                }
+            }
+        }
+
+        // A zero count leaves both the operand and the flags untouched.
+        if count > 0 {
+            let is_sign_set = result & sign_bit != 0;
+
+            // OF is only defined for single bit shifts, but the 8086 computes it the same way
+            // regardless of the count.
+            let overflow = match self {
+                ShiftLeft(_) | RotateLeft(_) | RotateThroughCarryLeft(_) => is_sign_set != carry,
+                ShiftRight(_) => (u32::from(i16::from(value) as u16) & sign_bit) != 0,
+                ShiftArithmeticRight(_) => false,
+                RotateRight(_) | RotateThroughCarryRight(_) => {
+                    is_sign_set != (result & (sign_bit >> 1) != 0)
+                }
+            };
+
+            let flags = store.flag_register_store_mut();
+
+            flags.write_flag(FlagRegister::Carry, carry);
+            flags.write_flag(FlagRegister::Overflow, overflow);
+
+            // Rotates only ever touch CF and OF.
+            if let ShiftLeft(_) | ShiftRight(_) | ShiftArithmeticRight(_) = self {
+                flags.write_flag(FlagRegister::Zero, result == 0);
+                flags.write_flag(FlagRegister::Sign, is_sign_set);
+                flags.write_flag(
+                    FlagRegister::Parity,
+                    (result & 0xFF).count_ones().is_multiple_of(2),
+                );
+            }
+
+            let result = if is_wide {
+                ImmediateValue::SignedWord(result as u16 as i16)
+            } else {
+                ImmediateValue::SignedByte(result as u8 as i8)
+            };
+
+            instruction.destination.write_value(result, is_wide, store);
+        }
+
+        let per_bit_cost = if let Some(Operand::Register(_)) = instruction.source {
+            4 * count
+        } else {
+            0
+        };
+
+        self.num_cycles() + per_bit_cost + if clock_penalty { 4 } else { 0 }
+    }
+}
@@ -0,0 +1,214 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::{AnyInstruction, Execute};
+use crate::register::Register;
+use crate::segment_register::SegmentRegister;
+use crate::store::Store;
+
+pub enum StackInstructions {
+    Push(AnyInstruction),
+    Pop(AnyInstruction),
+    PushFlags(AnyInstruction),
+    PopFlags(AnyInstruction),
+    PushAll(AnyInstruction),
+    PopAll(AnyInstruction),
+    Enter(AnyInstruction),
+    Leave(AnyInstruction),
+}
+
+impl StackInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            StackInstructions::Push(instruction)
+            | StackInstructions::Pop(instruction)
+            | StackInstructions::PushFlags(instruction)
+            | StackInstructions::PopFlags(instruction)
+            | StackInstructions::PushAll(instruction)
+            | StackInstructions::PopAll(instruction)
+            | StackInstructions::Enter(instruction)
+            | StackInstructions::Leave(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            StackInstructions::Push(instruction)
+            | StackInstructions::Pop(instruction)
+            | StackInstructions::PushFlags(instruction)
+            | StackInstructions::PopFlags(instruction)
+            | StackInstructions::PushAll(instruction)
+            | StackInstructions::PopAll(instruction)
+            | StackInstructions::Enter(instruction)
+            | StackInstructions::Leave(instruction) => instruction,
+        }
+    }
+
+    /// The order pusha pushes the registers in, which popa pops them back in reverse.
+    const ALL_REGISTERS: [Register; 8] = [
+        Register::Ax,
+        Register::Cx,
+        Register::Dx,
+        Register::Bx,
+        Register::Sp,
+        Register::Bp,
+        Register::Si,
+        Register::Di,
+    ];
+
+    /// Only the low five bits of the nesting level are significant.
+    fn nesting_level(instruction: &AnyInstruction) -> u8 {
+        let Some(Operand::Immediate(level)) = instruction.source else {
+            panic!("enter expects a nesting level");
+        };
+
+        (u16::from(level) & 0b1_1111) as u8
+    }
+}
+
+impl EstimatedCycleCount for StackInstructions {
+    fn num_cycles(&self) -> u32 {
+        match self {
+            StackInstructions::Push(instruction) => match instruction.destination {
+                Operand::Register(_) => 11,
+                Operand::SegmentRegister(_) | Operand::Immediate(_) => 10,
+                Operand::Memory(ea) => 16 + ea.num_cycles(),
+                _ => panic!("Invalid push operation"),
+            },
+            StackInstructions::Pop(instruction) => match instruction.destination {
+                Operand::Register(_) | Operand::SegmentRegister(_) => 8,
+                Operand::Memory(ea) => 17 + ea.num_cycles(),
+                _ => panic!("Invalid pop operation"),
+            },
+            StackInstructions::PushFlags(_) => 10,
+            StackInstructions::PopFlags(_) | StackInstructions::Leave(_) => 8,
+            StackInstructions::PushAll(_) => 36,
+            StackInstructions::PopAll(_) => 51,
+            StackInstructions::Enter(instruction) => match Self::nesting_level(instruction) {
+                0 => 15,
+                1 => 25,
+                level => 22 + 16 * (u32::from(level) - 1),
+            },
+        }
+    }
+}
+
+impl Execute for StackInstructions {
+    fn execute(&self, _reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        let clock_penalty = match self {
+            StackInstructions::Push(AnyInstruction { destination, .. }) => {
+                // The 8086 decrements sp before reading it, so push sp stores the new value.
+                if *destination == Operand::Register(Register::Sp) {
+                    let sp = store.register_store().read_word_from_register(Register::Sp);
+                    store.push_word(sp.wrapping_sub(2));
+
+                    false
+                } else {
+                    let (value, clock_penalty) = destination.to_immediate_value(true, store);
+                    store.push_word(value.into());
+
+                    clock_penalty
+                }
+            }
+            StackInstructions::Pop(AnyInstruction { destination, .. }) => {
+                let (value, clock_penalty) = store.pop_word();
+                destination.write_value(ImmediateValue::SignedWord(value as i16), true, store);
+
+                clock_penalty
+            }
+            StackInstructions::PushFlags(_) => {
+                let flags = store.flag_register_store().read_word();
+                store.push_word(flags);
+
+                false
+            }
+            StackInstructions::PopFlags(_) => {
+                let (flags, clock_penalty) = store.pop_word();
+                store.flag_register_store_mut().write_word(flags);
+
+                clock_penalty
+            }
+            StackInstructions::PushAll(_) => {
+                let sp = store.register_store().read_word_from_register(Register::Sp);
+
+                for register in Self::ALL_REGISTERS {
+                    // sp is pushed as it was before the first push.
+                    let value = if register == Register::Sp {
+                        sp
+                    } else {
+                        store.register_store().read_word_from_register(register)
+                    };
+
+                    store.push_word(value);
+                }
+
+                !sp.is_multiple_of(2)
+            }
+            StackInstructions::PopAll(_) => {
+                let mut clock_penalty = false;
+
+                for register in Self::ALL_REGISTERS.into_iter().rev() {
+                    let (value, is_unaligned) = store.pop_word();
+                    clock_penalty |= is_unaligned;
+
+                    // The pushed sp is skipped, it ends up wherever the pops leave it.
+                    if register != Register::Sp {
+                        store
+                            .register_store_mut()
+                            .write_word_to_register(register, value);
+                    }
+                }
+
+                clock_penalty
+            }
+            StackInstructions::Enter(instruction) => {
+                let (frame_size, _) = instruction.destination.to_immediate_value(true, store);
+                let nesting_level = Self::nesting_level(instruction);
+
+                let bp = store.register_store().read_word_from_register(Register::Bp);
+                store.push_word(bp);
+
+                let frame_pointer = store.register_store().read_word_from_register(Register::Sp);
+                let mut clock_penalty = !frame_pointer.is_multiple_of(2);
+
+                if nesting_level > 0 {
+                    // Copies the frame pointers of the enclosing procedures into the new frame.
+                    let mut bp = bp;
+
+                    for _ in 1..nesting_level {
+                        bp = bp.wrapping_sub(2);
+
+                        let (value, is_unaligned) =
+                            store.read_from_memory(SegmentRegister::Ss, bp, true);
+                        clock_penalty |= is_unaligned;
+                        store.push_word(value.into());
+                    }
+
+                    store.push_word(frame_pointer);
+                }
+
+                let registers = store.register_store_mut();
+                let sp = registers.read_word_from_register(Register::Sp);
+
+                registers.write_word_to_register(Register::Bp, frame_pointer);
+                registers.write_word_to_register(Register::Sp, sp.wrapping_sub(frame_size.into()));
+
+                clock_penalty
+            }
+            StackInstructions::Leave(_) => {
+                let registers = store.register_store_mut();
+                let bp = registers.read_word_from_register(Register::Bp);
+                registers.write_word_to_register(Register::Sp, bp);
+
+                let (bp, clock_penalty) = store.pop_word();
+                store
+                    .register_store_mut()
+                    .write_word_to_register(Register::Bp, bp);
+
+                clock_penalty
+            }
+        };
+
+        self.num_cycles() + if clock_penalty { 4 } else { 0 }
+    }
+}
@@ -0,0 +1,226 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::flag_register::FlagRegister;
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::prefix::Repeat;
+use crate::instructions::{AnyInstruction, Execute};
+use crate::memory::EffectiveAddress;
+use crate::register::Register;
+use crate::segment_register::SegmentRegister;
+use crate::store::Store;
+
+pub enum StringInstructions {
+    Move(AnyInstruction),
+    Compare(AnyInstruction),
+    Scan(AnyInstruction),
+    Load(AnyInstruction),
+    Store(AnyInstruction),
+    Input(AnyInstruction),
+    Output(AnyInstruction),
+}
+
+impl StringInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            StringInstructions::Move(instruction)
+            | StringInstructions::Compare(instruction)
+            | StringInstructions::Scan(instruction)
+            | StringInstructions::Load(instruction)
+            | StringInstructions::Store(instruction)
+            | StringInstructions::Input(instruction)
+            | StringInstructions::Output(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            StringInstructions::Move(instruction)
+            | StringInstructions::Compare(instruction)
+            | StringInstructions::Scan(instruction)
+            | StringInstructions::Load(instruction)
+            | StringInstructions::Store(instruction)
+            | StringInstructions::Input(instruction)
+            | StringInstructions::Output(instruction) => instruction,
+        }
+    }
+}
+
+impl StringInstructions {
+    /// Performs a single iteration, returning whether any of its memory accesses were unaligned
+    /// words.
+    fn execute_once(&self, store: &mut Store) -> bool {
+        use StringInstructions::*;
+
+        let instruction = self.instruction();
+        let is_wide = instruction.is_wide;
+
+        // The source honours segment overrides, but the destination is always `es:di`.
+        let source = Operand::Memory(EffectiveAddress::Register(Register::Si));
+        let destination = store.register_store().read_word_from_register(Register::Di);
+        let accumulator = if is_wide {
+            Operand::AccumulatorWide
+        } else {
+            Operand::Accumulator
+        };
+
+        let (clock_penalty, steps_source, steps_destination) = match self {
+            Move(_) => {
+                let (value, clock_penalty) = source.to_immediate_value(is_wide, store);
+                store.write_to_memory(SegmentRegister::Es, destination, is_wide, value);
+
+                (clock_penalty, true, true)
+            }
+            Compare(_) => {
+                let (lhs, clock_penalty_lhs) = source.to_immediate_value(is_wide, store);
+                let (rhs, clock_penalty_rhs) =
+                    store.read_from_memory(SegmentRegister::Es, destination, is_wide);
+
+                store.flag_register_store_mut().set_flags_on_op(lhs - rhs);
+
+                (clock_penalty_lhs || clock_penalty_rhs, true, true)
+            }
+            Scan(_) => {
+                let (lhs, _) = accumulator.to_immediate_value(is_wide, store);
+                let (rhs, clock_penalty) =
+                    store.read_from_memory(SegmentRegister::Es, destination, is_wide);
+
+                store.flag_register_store_mut().set_flags_on_op(lhs - rhs);
+
+                (clock_penalty, false, true)
+            }
+            Load(_) => {
+                let (value, clock_penalty) = source.to_immediate_value(is_wide, store);
+                accumulator.write_value(value, is_wide, store);
+
+                (clock_penalty, true, false)
+            }
+            Store(_) => {
+                let (value, _) = accumulator.to_immediate_value(is_wide, store);
+                store.write_to_memory(SegmentRegister::Es, destination, is_wide, value);
+
+                (false, false, true)
+            }
+            Input(_) => {
+                let port = store.register_store().read_word_from_register(Register::Dx);
+                let value = if is_wide {
+                    ImmediateValue::SignedWord(store.io_bus_mut().read_word(port) as i16)
+                } else {
+                    ImmediateValue::SignedByte(store.io_bus_mut().read_byte(port) as i8)
+                };
+
+                store.write_to_memory(SegmentRegister::Es, destination, is_wide, value);
+
+                (false, false, true)
+            }
+            Output(_) => {
+                let port = store.register_store().read_word_from_register(Register::Dx);
+                let (value, clock_penalty) = source.to_immediate_value(is_wide, store);
+
+                if is_wide {
+                    store.io_bus_mut().write_word(port, value.into());
+                } else {
+                    store.io_bus_mut().write_byte(
+                        port,
+                        value.try_into().expect("Not is wide but value is word"),
+                    );
+                }
+
+                (clock_penalty, true, false)
+            }
+        };
+
+        let step: u16 = if is_wide { 2 } else { 1 };
+        let is_decrementing = store
+            .flag_register_store()
+            .get_flag(FlagRegister::Direction);
+
+        let registers = store.register_store_mut();
+
+        for (register, should_step) in [
+            (Register::Si, steps_source),
+            (Register::Di, steps_destination),
+        ] {
+            if should_step {
+                let value = registers.read_word_from_register(register);
+
+                registers.write_word_to_register(
+                    register,
+                    if is_decrementing {
+                        value.wrapping_sub(step)
+                    } else {
+                        value.wrapping_add(step)
+                    },
+                );
+            }
+        }
+
+        clock_penalty
+    }
+
+    fn cycles_per_repetition(&self) -> u32 {
+        match self {
+            StringInstructions::Move(_) => 17,
+            StringInstructions::Compare(_) => 22,
+            StringInstructions::Scan(_) => 15,
+            StringInstructions::Load(_) => 13,
+            StringInstructions::Store(_) => 10,
+            StringInstructions::Input(_) | StringInstructions::Output(_) => 8,
+        }
+    }
+}
+
+impl EstimatedCycleCount for StringInstructions {
+    fn num_cycles(&self) -> u32 {
+        // Repeated forms cost a fixed setup, plus a per repetition cost which depends on CX and
+        // is charged when executing.
+        if self.instruction().prefixes.repeat.is_some() {
+            return 9;
+        }
+
+        match self {
+            StringInstructions::Move(_) => 18,
+            StringInstructions::Compare(_) => 22,
+            StringInstructions::Scan(_) => 15,
+            StringInstructions::Load(_) => 12,
+            StringInstructions::Store(_) => 11,
+            StringInstructions::Input(_) | StringInstructions::Output(_) => 14,
+        }
+    }
+}
+
+impl Execute for StringInstructions {
+    fn execute(&self, _reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        let Some(repeat) = self.instruction().prefixes.repeat else {
+            return self.num_cycles() + if self.execute_once(store) { 4 } else { 0 };
+        };
+
+        let is_comparison = matches!(
+            self,
+            StringInstructions::Compare(_) | StringInstructions::Scan(_)
+        );
+
+        let mut num_cycles = self.num_cycles();
+
+        while store.register_store().read_word_from_register(Register::Cx) != 0 {
+            let clock_penalty = self.execute_once(store);
+
+            let registers = store.register_store_mut();
+            let count = registers.read_word_from_register(Register::Cx);
+            registers.write_word_to_register(Register::Cx, count - 1);
+
+            num_cycles += self.cycles_per_repetition() + if clock_penalty { 4 } else { 0 };
+
+            if is_comparison {
+                let is_zero = store.flag_register_store().get_flag(FlagRegister::Zero);
+
+                match repeat {
+                    Repeat::WhileZero if !is_zero => break,
+                    Repeat::WhileNotZero if is_zero => break,
+                    _ => {}
+                }
+            }
+        }
+
+        num_cycles
+    }
+}
@@ -0,0 +1,475 @@
+use crate::cpu::CpuModel;
+use crate::cpu::CpuModel::Intel80186;
+use crate::instructions::generic::Op;
+use crate::prelude::*;
+use std::sync::OnceLock;
+
+/// One line of the encoding table, written the way the Intel manual lays out its instruction
+/// formats.
+///
+/// `format` lists the bits of the opcode byte and, for instructions with one, the ModRM byte,
+/// followed by the fields that trail them. Bits are either fixed `0`s and `1`s or the named
+/// fields `d`, `w`, `s`, `v`, `reg`, `sr`, `esc`, `mod` and `r/m`. The trailing fields are
+/// `data` (a byte, a word, or a byte sign extended by `s`, going by `w`), `data8`, `udata8`,
+/// `data16`, `addr`, `ip-inc8`, `ip-inc` and `ptr`. `w=1` marks an operation that is wide
+/// without a `w` bit.
+///
+/// `operands` lists the operands in the order they are written: `reg`, `r/m`, `m` (an `r/m`
+/// that has to be in memory), `sr`, `acc`, `dx`, `cl`, `count` (`cl` or `1`, going by `v`) and
+/// `esc` (the coprocessor opcode) come out of the bits, while `imm`, `mem`, `rel` and `ptr`
+/// take the trailing fields in turn. A set `d` bit swaps the first two.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EncodingSpec {
+    pub op: Op,
+    pub format: &'static str,
+    pub operands: &'static str,
+    /// The first processor that decodes the encoding.
+    pub since: CpuModel,
+}
+
+impl EncodingSpec {
+    const fn since(self, model: CpuModel) -> Self {
+        Self {
+            since: model,
+            ..self
+        }
+    }
+
+    /// Every opcode byte the encoding starts with.
+    pub fn opcodes(&self) -> impl Iterator<Item = Byte> {
+        let (mask, value) = Layout::parse(self).fixed[0];
+
+        (0..=Byte::MAX).filter(move |opcode| opcode & mask == value)
+    }
+
+    /// Whether the encoding is followed by a ModRM byte.
+    pub fn has_modrm(&self) -> bool {
+        Layout::parse(self).has_modrm()
+    }
+}
+
+const fn spec(op: Op, format: &'static str, operands: &'static str) -> EncodingSpec {
+    EncodingSpec {
+        op,
+        format,
+        operands,
+        since: CpuModel::Intel8086,
+    }
+}
+
+/// Every encoding the decoder knows, tried in order. Prefixes, and the opcodes the 8086 runs
+/// as aliases of their neighbours, are handled ahead of the table.
+pub static ENCODINGS: &[EncodingSpec] = &[
+    // Data transfer
+    spec(Op::Mov, "100010 d w mod reg r/m", "r/m, reg"),
+    spec(Op::Mov, "1100011 w mod 000 r/m data", "r/m, imm"),
+    spec(Op::Mov, "1011 w reg data", "reg, imm"),
+    spec(Op::Mov, "1010000 w addr", "acc, mem"),
+    spec(Op::Mov, "1010001 w addr", "mem, acc"),
+    spec(Op::Mov, "100011 d 0 mod 0 sr r/m w=1", "r/m, sr"),
+    spec(Op::Push, "11111111 mod 110 r/m w=1", "r/m"),
+    spec(Op::Push, "01010 reg w=1", "reg"),
+    spec(Op::Push, "000 sr 110 w=1", "sr"),
+    spec(Op::Push, "01101000 data w=1", "imm").since(Intel80186),
+    spec(Op::Push, "01101010 data8 w=1", "imm").since(Intel80186),
+    spec(Op::Pop, "10001111 mod 000 r/m w=1", "r/m"),
+    spec(Op::Pop, "01011 reg w=1", "reg"),
+    spec(Op::Pop, "000 sr 111 w=1", "sr"),
+    spec(Op::Pusha, "01100000 w=1", "").since(Intel80186),
+    spec(Op::Popa, "01100001 w=1", "").since(Intel80186),
+    spec(Op::Nop, "10010000", ""),
+    spec(Op::Xchg, "1000011 w mod reg r/m", "reg, r/m"),
+    spec(Op::Xchg, "10010 reg w=1", "acc, reg"),
+    spec(Op::In, "1110010 w udata8", "acc, imm"),
+    spec(Op::In, "1110110 w", "acc, dx"),
+    spec(Op::Out, "1110011 w udata8", "imm, acc"),
+    spec(Op::Out, "1110111 w", "dx, acc"),
+    spec(Op::Xlat, "11010111", ""),
+    spec(Op::Lea, "10001101 mod reg r/m w=1", "reg, m"),
+    spec(Op::Lds, "11000101 mod reg r/m w=1", "reg, m"),
+    spec(Op::Les, "11000100 mod reg r/m w=1", "reg, m"),
+    spec(Op::Lahf, "10011111", ""),
+    spec(Op::Sahf, "10011110", ""),
+    spec(Op::Pushf, "10011100 w=1", ""),
+    spec(Op::Popf, "10011101 w=1", ""),
+    // Arithmetic
+    spec(Op::Add, "000000 d w mod reg r/m", "r/m, reg"),
+    spec(Op::Add, "100000 s w mod 000 r/m data", "r/m, imm"),
+    spec(Op::Add, "0000010 w data", "acc, imm"),
+    spec(Op::Adc, "000100 d w mod reg r/m", "r/m, reg"),
+    spec(Op::Adc, "100000 s w mod 010 r/m data", "r/m, imm"),
+    spec(Op::Adc, "0001010 w data", "acc, imm"),
+    spec(Op::Inc, "1111111 w mod 000 r/m", "r/m"),
+    spec(Op::Inc, "01000 reg w=1", "reg"),
+    spec(Op::Aaa, "00110111", ""),
+    spec(Op::Daa, "00100111", ""),
+    spec(Op::Sub, "001010 d w mod reg r/m", "r/m, reg"),
+    spec(Op::Sub, "100000 s w mod 101 r/m data", "r/m, imm"),
+    spec(Op::Sub, "0010110 w data", "acc, imm"),
+    spec(Op::Sbb, "000110 d w mod reg r/m", "r/m, reg"),
+    spec(Op::Sbb, "100000 s w mod 011 r/m data", "r/m, imm"),
+    spec(Op::Sbb, "0001110 w data", "acc, imm"),
+    spec(Op::Dec, "1111111 w mod 001 r/m", "r/m"),
+    spec(Op::Dec, "01001 reg w=1", "reg"),
+    spec(Op::Neg, "1111011 w mod 011 r/m", "r/m"),
+    spec(Op::Cmp, "001110 d w mod reg r/m", "r/m, reg"),
+    spec(Op::Cmp, "100000 s w mod 111 r/m data", "r/m, imm"),
+    spec(Op::Cmp, "0011110 w data", "acc, imm"),
+    spec(Op::Aas, "00111111", ""),
+    spec(Op::Das, "00101111", ""),
+    spec(Op::Mul, "1111011 w mod 100 r/m", "r/m"),
+    spec(Op::Imul, "1111011 w mod 101 r/m", "r/m"),
+    spec(Op::Imul, "011010 s 1 mod reg r/m data w=1", "reg, r/m, imm").since(Intel80186),
+    spec(Op::Aam, "11010100 udata8", "imm"),
+    spec(Op::Div, "1111011 w mod 110 r/m", "r/m"),
+    spec(Op::Idiv, "1111011 w mod 111 r/m", "r/m"),
+    spec(Op::Aad, "11010101 udata8", "imm"),
+    spec(Op::Cbw, "10011000", ""),
+    spec(Op::Cwd, "10011001", ""),
+    spec(Op::Bound, "01100010 mod reg r/m w=1", "reg, m").since(Intel80186),
+    // Logic
+    spec(Op::Not, "1111011 w mod 010 r/m", "r/m"),
+    spec(Op::Shl, "110100 v w mod 100 r/m", "r/m, count"),
+    spec(Op::Shl, "110100 v w mod 110 r/m", "r/m, count"),
+    spec(Op::Shr, "110100 v w mod 101 r/m", "r/m, count"),
+    spec(Op::Sar, "110100 v w mod 111 r/m", "r/m, count"),
+    spec(Op::Rol, "110100 v w mod 000 r/m", "r/m, count"),
+    spec(Op::Ror, "110100 v w mod 001 r/m", "r/m, count"),
+    spec(Op::Rcl, "110100 v w mod 010 r/m", "r/m, count"),
+    spec(Op::Rcr, "110100 v w mod 011 r/m", "r/m, count"),
+    spec(Op::Shl, "1100000 w mod 100 r/m udata8", "r/m, imm").since(Intel80186),
+    spec(Op::Shl, "1100000 w mod 110 r/m udata8", "r/m, imm").since(Intel80186),
+    spec(Op::Shr, "1100000 w mod 101 r/m udata8", "r/m, imm").since(Intel80186),
+    spec(Op::Sar, "1100000 w mod 111 r/m udata8", "r/m, imm").since(Intel80186),
+    spec(Op::Rol, "1100000 w mod 000 r/m udata8", "r/m, imm").since(Intel80186),
+    spec(Op::Ror, "1100000 w mod 001 r/m udata8", "r/m, imm").since(Intel80186),
+    spec(Op::Rcl, "1100000 w mod 010 r/m udata8", "r/m, imm").since(Intel80186),
+    spec(Op::Rcr, "1100000 w mod 011 r/m udata8", "r/m, imm").since(Intel80186),
+    spec(Op::And, "001000 d w mod reg r/m", "r/m, reg"),
+    spec(Op::And, "100000 s w mod 100 r/m data", "r/m, imm"),
+    spec(Op::And, "0010010 w data", "acc, imm"),
+    spec(Op::Test, "1000010 w mod reg r/m", "r/m, reg"),
+    spec(Op::Test, "1111011 w mod 000 r/m data", "r/m, imm"),
+    // The 8086 doesn't decode the low bit of the reg field for test.
+    spec(Op::Test, "1111011 w mod 001 r/m data", "r/m, imm"),
+    spec(Op::Test, "1010100 w data", "acc, imm"),
+    spec(Op::Or, "000010 d w mod reg r/m", "r/m, reg"),
+    spec(Op::Or, "100000 s w mod 001 r/m data", "r/m, imm"),
+    spec(Op::Or, "0000110 w data", "acc, imm"),
+    spec(Op::Xor, "001100 d w mod reg r/m", "r/m, reg"),
+    spec(Op::Xor, "100000 s w mod 110 r/m data", "r/m, imm"),
+    spec(Op::Xor, "0011010 w data", "acc, imm"),
+    // String manipulation
+    spec(Op::Movs, "1010010 w", ""),
+    spec(Op::Cmps, "1010011 w", ""),
+    spec(Op::Scas, "1010111 w", ""),
+    spec(Op::Lods, "1010110 w", ""),
+    spec(Op::Stos, "1010101 w", ""),
+    spec(Op::Ins, "0110110 w", "").since(Intel80186),
+    spec(Op::Outs, "0110111 w", "").since(Intel80186),
+    // Control transfer
+    spec(Op::Call, "11101000 ip-inc w=1", "rel"),
+    spec(Op::Call, "11111111 mod 010 r/m w=1", "r/m"),
+    spec(Op::CallFar, "10011010 ptr w=1", "ptr"),
+    spec(Op::CallFar, "11111111 mod 011 r/m w=1", "m"),
+    spec(Op::Jmp, "11101001 ip-inc w=1", "rel"),
+    spec(Op::Jmp, "11101011 ip-inc8", "rel"),
+    spec(Op::Jmp, "11111111 mod 100 r/m w=1", "r/m"),
+    spec(Op::JmpFar, "11101010 ptr w=1", "ptr"),
+    spec(Op::JmpFar, "11111111 mod 101 r/m w=1", "m"),
+    spec(Op::Ret, "11000011 w=1", ""),
+    spec(Op::Ret, "11000010 data16 w=1", "imm"),
+    spec(Op::Retf, "11001011 w=1", ""),
+    spec(Op::Retf, "11001010 data16 w=1", "imm"),
+    spec(Op::Je, "01110100 ip-inc8", "rel"),
+    spec(Op::Jl, "01111100 ip-inc8", "rel"),
+    spec(Op::Jle, "01111110 ip-inc8", "rel"),
+    spec(Op::Jb, "01110010 ip-inc8", "rel"),
+    spec(Op::Jbe, "01110110 ip-inc8", "rel"),
+    spec(Op::Jp, "01111010 ip-inc8", "rel"),
+    spec(Op::Jo, "01110000 ip-inc8", "rel"),
+    spec(Op::Js, "01111000 ip-inc8", "rel"),
+    spec(Op::Jne, "01110101 ip-inc8", "rel"),
+    spec(Op::Jnl, "01111101 ip-inc8", "rel"),
+    spec(Op::Jnle, "01111111 ip-inc8", "rel"),
+    spec(Op::Jnb, "01110011 ip-inc8", "rel"),
+    spec(Op::Jnbe, "01110111 ip-inc8", "rel"),
+    spec(Op::Jnp, "01111011 ip-inc8", "rel"),
+    spec(Op::Jno, "01110001 ip-inc8", "rel"),
+    spec(Op::Jns, "01111001 ip-inc8", "rel"),
+    spec(Op::Loop, "11100010 ip-inc8", "rel"),
+    spec(Op::Loope, "11100001 ip-inc8", "rel"),
+    spec(Op::Loopne, "11100000 ip-inc8", "rel"),
+    spec(Op::Jcxz, "11100011 ip-inc8", "rel"),
+    spec(Op::Int, "11001101 udata8", "imm"),
+    spec(Op::Int3, "11001100", ""),
+    spec(Op::Into, "11001110", ""),
+    spec(Op::Iret, "11001111", ""),
+    spec(Op::Enter, "11001000 data16 udata8 w=1", "imm, imm").since(Intel80186),
+    spec(Op::Leave, "11001001 w=1", "").since(Intel80186),
+    // Processor control
+    spec(Op::Clc, "11111000", ""),
+    spec(Op::Cmc, "11110101", ""),
+    spec(Op::Stc, "11111001", ""),
+    spec(Op::Cld, "11111100", ""),
+    spec(Op::Std, "11111101", ""),
+    spec(Op::Cli, "11111010", ""),
+    spec(Op::Sti, "11111011", ""),
+    spec(Op::Hlt, "11110100", ""),
+    spec(Op::Wait, "10011011", ""),
+    spec(Op::Esc, "11011 esc mod reg r/m w=1", "esc, r/m"),
+];
+
+/// A named field of the opcode or ModRM byte.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Field {
+    Direction,
+    Wide,
+    SignExtend,
+    Variable,
+    Mode,
+    Register,
+    SegmentRegister,
+    RegisterOrMemory,
+    Escape,
+}
+
+impl Field {
+    fn parse(token: &str) -> Option<Self> {
+        Some(match token {
+            "d" => Field::Direction,
+            "w" => Field::Wide,
+            "s" => Field::SignExtend,
+            "v" => Field::Variable,
+            "mod" => Field::Mode,
+            "reg" => Field::Register,
+            "sr" => Field::SegmentRegister,
+            "r/m" => Field::RegisterOrMemory,
+            "esc" => Field::Escape,
+            _ => return None,
+        })
+    }
+
+    fn width(self) -> u8 {
+        match self {
+            Field::Direction | Field::Wide | Field::SignExtend | Field::Variable => 1,
+            Field::Mode | Field::SegmentRegister => 2,
+            Field::Register | Field::RegisterOrMemory | Field::Escape => 3,
+        }
+    }
+}
+
+/// Where a field sits in the instruction's leading bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct FieldSlot {
+    pub field: Field,
+    pub byte: usize,
+    pub shift: u8,
+    pub width: u8,
+}
+
+impl FieldSlot {
+    pub fn extract(&self, bytes: &[Byte]) -> Byte {
+        (bytes[self.byte] >> self.shift) & ((1 << self.width) - 1)
+    }
+}
+
+/// A field that follows the opcode and ModRM bytes, along with any displacement.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Trailing {
+    Data,
+    Data8,
+    UnsignedData8,
+    Data16,
+    Address,
+    IpIncrement8,
+    IpIncrement,
+    FarPointer,
+}
+
+impl Trailing {
+    fn parse(token: &str) -> Option<Self> {
+        Some(match token {
+            "data" => Trailing::Data,
+            "data8" => Trailing::Data8,
+            "udata8" => Trailing::UnsignedData8,
+            "data16" => Trailing::Data16,
+            "addr" => Trailing::Address,
+            "ip-inc8" => Trailing::IpIncrement8,
+            "ip-inc" => Trailing::IpIncrement,
+            "ptr" => Trailing::FarPointer,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum OperandSlot {
+    Register,
+    RegisterOrMemory,
+    Memory,
+    SegmentRegister,
+    Accumulator,
+    Dx,
+    Cl,
+    Count,
+    Escape,
+    /// The next of the trailing fields.
+    Trailing,
+}
+
+impl OperandSlot {
+    fn parse(token: &str) -> Option<Self> {
+        Some(match token {
+            "reg" => OperandSlot::Register,
+            "r/m" => OperandSlot::RegisterOrMemory,
+            "m" => OperandSlot::Memory,
+            "sr" => OperandSlot::SegmentRegister,
+            "acc" => OperandSlot::Accumulator,
+            "dx" => OperandSlot::Dx,
+            "cl" => OperandSlot::Cl,
+            "count" => OperandSlot::Count,
+            "esc" => OperandSlot::Escape,
+            "imm" | "mem" | "rel" | "ptr" => OperandSlot::Trailing,
+            _ => return None,
+        })
+    }
+}
+
+/// An [`EncodingSpec`] taken apart into what the decoder needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Layout {
+    /// The mask and value of the fixed bits of the opcode byte and of the ModRM byte, when
+    /// there is one.
+    pub fixed: Vec<(Byte, Byte)>,
+    pub fields: Vec<FieldSlot>,
+    pub trailing: Vec<Trailing>,
+    pub operands: Vec<OperandSlot>,
+    pub is_wide: Wide,
+}
+
+impl Layout {
+    /// Panics on a malformed spec, as the table is fixed at compile time.
+    fn parse(spec: &EncodingSpec) -> Self {
+        let mut layout = Layout {
+            fixed: Vec::new(),
+            fields: Vec::new(),
+            trailing: Vec::new(),
+            operands: Vec::new(),
+            is_wide: false,
+        };
+        let mut bit = 0u8;
+
+        for token in spec.format.split_whitespace() {
+            let (byte, shift) = (usize::from(bit / 8), 8 - bit % 8);
+
+            if token.chars().all(|c| c == '0' || c == '1') {
+                let width = token.len() as u8;
+                let shift = shift
+                    .checked_sub(width)
+                    .unwrap_or_else(|| panic!("{token} crosses a byte in {}", spec.format));
+
+                if byte == layout.fixed.len() {
+                    layout.fixed.push((0, 0));
+                }
+
+                let mask = ((1u16 << width) - 1) as u8;
+                let value = Byte::from_str_radix(token, 2).unwrap();
+                layout.fixed[byte].0 |= mask << shift;
+                layout.fixed[byte].1 |= value << shift;
+                bit += width;
+            } else if let Some(field) = Field::parse(token) {
+                let width = field.width();
+                let shift = shift
+                    .checked_sub(width)
+                    .unwrap_or_else(|| panic!("{token} crosses a byte in {}", spec.format));
+
+                if byte == layout.fixed.len() {
+                    layout.fixed.push((0, 0));
+                }
+
+                layout.fields.push(FieldSlot {
+                    field,
+                    byte,
+                    shift,
+                    width,
+                });
+                bit += width;
+            } else if let Some(trailing) = Trailing::parse(token) {
+                layout.trailing.push(trailing);
+            } else if token == "w=1" {
+                layout.is_wide = true;
+            } else {
+                panic!("Unknown field {token} in {}", spec.format);
+            }
+        }
+
+        assert!(
+            bit == 8 || bit == 16,
+            "{} doesn't fill its leading bytes",
+            spec.format
+        );
+
+        layout.operands = spec
+            .operands
+            .split(',')
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                OperandSlot::parse(token)
+                    .unwrap_or_else(|| panic!("Unknown operand {token} in {}", spec.operands))
+            })
+            .collect();
+
+        layout
+    }
+
+    pub fn has_modrm(&self) -> bool {
+        self.fixed.len() == 2
+    }
+
+    pub fn field(&self, field: Field) -> Option<&FieldSlot> {
+        self.fields.iter().find(|slot| slot.field == field)
+    }
+}
+
+/// The parsed table, indexed by the opcode byte.
+pub(crate) struct Table {
+    pub layouts: Vec<Layout>,
+    by_opcode: Vec<Vec<usize>>,
+}
+
+impl Table {
+    pub fn get() -> &'static Table {
+        static TABLE: OnceLock<Table> = OnceLock::new();
+
+        TABLE.get_or_init(|| {
+            let layouts: Vec<Layout> = ENCODINGS.iter().map(Layout::parse).collect();
+            let mut by_opcode = vec![Vec::new(); 256];
+
+            for (index, layout) in layouts.iter().enumerate() {
+                let (mask, value) = layout.fixed[0];
+
+                for opcode in 0..=Byte::MAX {
+                    if opcode & mask == value {
+                        by_opcode[usize::from(opcode)].push(index);
+                    }
+                }
+            }
+
+            Table { layouts, by_opcode }
+        })
+    }
+
+    /// The encodings starting with `opcode` that `model` decodes, in table order.
+    pub fn candidates(
+        &self,
+        opcode: Byte,
+        model: CpuModel,
+    ) -> impl Iterator<Item = (&'static EncodingSpec, &Layout)> + '_ {
+        self.by_opcode[usize::from(opcode)]
+            .iter()
+            .map(|&index| (&ENCODINGS[index], &self.layouts[index]))
+            .filter(move |(spec, _)| spec.since <= model)
+    }
+}
@@ -0,0 +1,196 @@
+use crate::cursor::ByteCursor;
+use crate::cycle::EstimatedCycleCount;
+use crate::flag_register::FlagRegister;
+use crate::instructions::operands::{ImmediateValue, Operand};
+use crate::instructions::{AnyInstruction, Execute};
+use crate::register::Register;
+use crate::segment_register::SegmentRegister;
+use crate::store::Store;
+
+pub enum TransferInstructions {
+    Exchange(AnyInstruction),
+    Translate(AnyInstruction),
+    LoadEffectiveAddress(AnyInstruction),
+    LoadPointerUsingDs(AnyInstruction),
+    LoadPointerUsingEs(AnyInstruction),
+    LoadAhWithFlags(AnyInstruction),
+    StoreAhIntoFlags(AnyInstruction),
+    ConvertByteToWord(AnyInstruction),
+    ConvertWordToDoubleWord(AnyInstruction),
+}
+
+impl TransferInstructions {
+    pub fn instruction(&self) -> &AnyInstruction {
+        match self {
+            TransferInstructions::Exchange(instruction)
+            | TransferInstructions::Translate(instruction)
+            | TransferInstructions::LoadEffectiveAddress(instruction)
+            | TransferInstructions::LoadPointerUsingDs(instruction)
+            | TransferInstructions::LoadPointerUsingEs(instruction)
+            | TransferInstructions::LoadAhWithFlags(instruction)
+            | TransferInstructions::StoreAhIntoFlags(instruction)
+            | TransferInstructions::ConvertByteToWord(instruction)
+            | TransferInstructions::ConvertWordToDoubleWord(instruction) => instruction,
+        }
+    }
+
+    pub fn instruction_mut(&mut self) -> &mut AnyInstruction {
+        match self {
+            TransferInstructions::Exchange(instruction)
+            | TransferInstructions::Translate(instruction)
+            | TransferInstructions::LoadEffectiveAddress(instruction)
+            | TransferInstructions::LoadPointerUsingDs(instruction)
+            | TransferInstructions::LoadPointerUsingEs(instruction)
+            | TransferInstructions::LoadAhWithFlags(instruction)
+            | TransferInstructions::StoreAhIntoFlags(instruction)
+            | TransferInstructions::ConvertByteToWord(instruction)
+            | TransferInstructions::ConvertWordToDoubleWord(instruction) => instruction,
+        }
+    }
+}
+
+impl EstimatedCycleCount for TransferInstructions {
+    fn num_cycles(&self) -> u32 {
+        match self {
+            TransferInstructions::Exchange(instruction) => {
+                match (instruction.destination, instruction.source) {
+                    (Operand::AccumulatorWide, _) => 3,
+                    (_, Some(Operand::Memory(ea))) => 17 + ea.num_cycles(),
+                    _ => 4,
+                }
+            }
+            TransferInstructions::Translate(_) => 11,
+            TransferInstructions::LoadEffectiveAddress(instruction) => match instruction.source {
+                Some(Operand::Memory(ea)) => 2 + ea.num_cycles(),
+                _ => panic!("Invalid LEA operation"),
+            },
+            TransferInstructions::LoadPointerUsingDs(instruction)
+            | TransferInstructions::LoadPointerUsingEs(instruction) => match instruction.source {
+                Some(Operand::Memory(ea)) => 16 + ea.num_cycles(),
+                _ => panic!("Invalid far pointer load"),
+            },
+            TransferInstructions::LoadAhWithFlags(_)
+            | TransferInstructions::StoreAhIntoFlags(_) => 4,
+            TransferInstructions::ConvertByteToWord(_) => 2,
+            TransferInstructions::ConvertWordToDoubleWord(_) => 5,
+        }
+    }
+}
+
+impl Execute for TransferInstructions {
+    fn execute(&self, _reader: &mut impl ByteCursor, store: &mut Store) -> u32 {
+        use FlagRegister::*;
+
+        let instruction = self.instruction();
+        let is_wide = instruction.is_wide;
+
+        let clock_penalty = match self {
+            TransferInstructions::Exchange(AnyInstruction {
+                source: Some(source),
+                destination,
+                ..
+            }) => {
+                let (destination_value, clock_penalty_destination) =
+                    destination.to_immediate_value(is_wide, store);
+                let (source_value, clock_penalty_source) =
+                    source.to_immediate_value(is_wide, store);
+
+                destination.write_value(source_value, is_wide, store);
+                source.write_value(destination_value, is_wide, store);
+
+                clock_penalty_destination || clock_penalty_source
+            }
+            TransferInstructions::Translate(_) => {
+                let registers = store.register_store();
+                let offset = registers
+                    .read_word_from_register(Register::Bx)
+                    .wrapping_add(u16::from(registers.read_byte_from_register(Register::Al)));
+                let segment = instruction
+                    .prefixes
+                    .segment_override
+                    .unwrap_or(SegmentRegister::Ds);
+
+                let (value, _) = store.read_from_memory(segment, offset, false);
+                Operand::Accumulator.write_value(value, false, store);
+
+                false
+            }
+            TransferInstructions::LoadEffectiveAddress(AnyInstruction {
+                source: Some(Operand::Memory(address)),
+                destination,
+                ..
+            }) => {
+                let offset = store
+                    .memory_store()
+                    .effective_address_to_address(*address, store.register_store());
+
+                destination.write_value(ImmediateValue::SignedWord(offset as i16), true, store);
+
+                false
+            }
+            TransferInstructions::LoadPointerUsingDs(AnyInstruction {
+                source: Some(Operand::Memory(address)),
+                destination,
+                ..
+            })
+            | TransferInstructions::LoadPointerUsingEs(AnyInstruction {
+                source: Some(Operand::Memory(address)),
+                destination,
+                ..
+            }) => {
+                let ((segment, offset), clock_penalty) =
+                    store.read_far_pointer_from_effective_memory_address(*address);
+
+                let segment_register = if let TransferInstructions::LoadPointerUsingDs(_) = self {
+                    SegmentRegister::Ds
+                } else {
+                    SegmentRegister::Es
+                };
+
+                destination.write_value(ImmediateValue::SignedWord(offset as i16), true, store);
+                store
+                    .segment_register_store_mut()
+                    .write_word_to_segment_register(segment_register, segment);
+
+                clock_penalty
+            }
+            TransferInstructions::LoadAhWithFlags(_) => {
+                let flags = store.flag_register_store().read_word() as u8;
+                store
+                    .register_store_mut()
+                    .write_byte_to_register(Register::Ah, flags);
+
+                false
+            }
+            TransferInstructions::StoreAhIntoFlags(_) => {
+                let ah = store.register_store().read_byte_from_register(Register::Ah);
+                let flags = store.flag_register_store_mut();
+
+                for flag in [Sign, Zero, AuxiliaryCarry, Parity, Carry] {
+                    flags.write_flag(flag, ah & (1 << flag.to_memory_address()) != 0);
+                }
+
+                false
+            }
+            TransferInstructions::ConvertByteToWord(_) => {
+                let registers = store.register_store_mut();
+                let al = registers.read_byte_from_register(Register::Al) as i8;
+
+                registers.write_word_to_register(Register::Ax, i16::from(al) as u16);
+
+                false
+            }
+            TransferInstructions::ConvertWordToDoubleWord(_) => {
+                let registers = store.register_store_mut();
+                let ax = registers.read_word_from_register(Register::Ax) as i16;
+
+                registers.write_word_to_register(Register::Dx, if ax < 0 { 0xFFFF } else { 0 });
+
+                false
+            }
+            _ => panic!("Invalid transfer operation"),
+        };
+
+        self.num_cycles() + if clock_penalty { 4 } else { 0 }
+    }
+}
@@ -0,0 +1,93 @@
+use std::any::Any;
+use std::fmt::Debug;
+
+/// The devices `in` and `out` talk to. Implement this to attach emulated hardware to a `Store`.
+pub trait IoBus: Any + Debug {
+    fn read_byte(&mut self, port: u16) -> u8;
+
+    fn write_byte(&mut self, port: u16, value: u8);
+
+    /// Word accesses reach the low byte at `port` and the high byte at the port after it.
+    fn read_word(&mut self, port: u16) -> u16 {
+        let low = self.read_byte(port);
+        let high = self.read_byte(port.wrapping_add(1));
+
+        u16::from_le_bytes([low, high])
+    }
+
+    fn write_word(&mut self, port: u16, value: u16) {
+        let [low, high] = value.to_le_bytes();
+
+        self.write_byte(port, low);
+        self.write_byte(port.wrapping_add(1), high);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PortAccessKind {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PortAccess {
+    pub kind: PortAccessKind,
+    pub port: u16,
+    pub is_wide: bool,
+    pub value: u16,
+}
+
+/// The bus a `Store` starts out with. Nothing is attached to it, so reads see the floating bus
+/// as all ones, and every access is recorded for inspection.
+#[derive(Debug, Default)]
+pub struct RecordingIoBus {
+    accesses: Vec<PortAccess>,
+}
+
+impl RecordingIoBus {
+    pub fn accesses(&self) -> &[PortAccess] {
+        &self.accesses
+    }
+}
+
+impl IoBus for RecordingIoBus {
+    fn read_byte(&mut self, port: u16) -> u8 {
+        self.accesses.push(PortAccess {
+            kind: PortAccessKind::Read,
+            port,
+            is_wide: false,
+            value: 0xFF,
+        });
+
+        0xFF
+    }
+
+    fn write_byte(&mut self, port: u16, value: u8) {
+        self.accesses.push(PortAccess {
+            kind: PortAccessKind::Write,
+            port,
+            is_wide: false,
+            value: u16::from(value),
+        });
+    }
+
+    fn read_word(&mut self, port: u16) -> u16 {
+        self.accesses.push(PortAccess {
+            kind: PortAccessKind::Read,
+            port,
+            is_wide: true,
+            value: 0xFFFF,
+        });
+
+        0xFFFF
+    }
+
+    fn write_word(&mut self, port: u16, value: u16) {
+        self.accesses.push(PortAccess {
+            kind: PortAccessKind::Write,
+            port,
+            is_wide: true,
+            value,
+        });
+    }
+}
@@ -1,7 +1,12 @@
+mod assembler;
+pub mod cpu;
+pub mod cursor;
 pub mod cycle;
+pub mod error;
 pub mod flag_register;
 pub mod helpers;
 mod instructions;
+pub mod io_bus;
 pub mod memory;
 mod mode;
 pub mod prelude;
@@ -9,53 +14,137 @@ pub mod register;
 pub mod segment_register;
 pub mod store;
 
+pub use crate::assembler::assemble;
+use crate::cpu::CpuModel;
+use crate::cursor::ByteCursor;
+use crate::error::DecodeErrorReason;
+pub use crate::error::{AssembleError, DecodeError, EncodeError};
 use crate::flag_register::FlagRegisterManager;
 pub use crate::instructions::decode::Instructions;
-pub use crate::instructions::Instruction;
+pub use crate::instructions::encode::EncodingChoice;
+pub use crate::instructions::encoding::Encoding;
+pub use crate::instructions::generic::{Instruction, Op};
+pub use crate::instructions::operands::{ImmediateValue, Operand};
+pub use crate::instructions::prefix::{Prefixes, Repeat};
+pub use crate::instructions::table::{EncodingSpec, ENCODINGS};
+pub use crate::instructions::Execute;
 use crate::memory::MemoryManager;
 use crate::register::RegisterManager;
 use crate::segment_register::SegmentRegisterManager;
 pub use crate::store::Store;
 use byteorder::ReadBytesExt;
 pub use prelude::*;
-use std::fs::File;
-use std::io::{BufReader, BufWriter, Write};
+use std::io::{BufWriter, Cursor, SeekFrom, Write};
 
-pub fn simulate(mut reader: BufReader<File>, store: &mut Store) -> (BufReader<File>, u32) {
+pub fn simulate<R: ByteCursor>(mut reader: R, store: &mut Store) -> Result<(R, u32), DecodeError> {
     let mut num_cpu_cycles = 0;
 
-    loop {
+    while store.stop_reason().is_none() {
         let Ok(instruction_byte) = reader.read_u8() else {
             break;
         };
 
-        let instruction = Instructions::read(&mut reader, instruction_byte);
+        let instruction = Instructions::read(&mut reader, instruction_byte, store.cpu_model())?;
+
+        // The 80386 is only decoded for, so whatever its size prefixes widen stops the run.
+        let prefixes = instruction.instruction().prefixes;
+
+        if prefixes.operand_size_override || prefixes.address_size_override {
+            let encoding = instruction.encoding();
+
+            return Err(DecodeError {
+                offset: encoding.offset(),
+                bytes: encoding.bytes().to_vec(),
+                reason: DecodeErrorReason::NotSimulated,
+            });
+        }
 
         num_cpu_cycles += instruction.execute(&mut reader, store);
     }
 
-    (reader, num_cpu_cycles)
+    Ok((reader, num_cpu_cycles))
+}
+
+pub fn decode(reader: impl ByteCursor) -> Result<String, DecodeError> {
+    decode_for_model(reader, CpuModel::default())
+}
+
+/// Decodes a program already in memory, reading it in place.
+pub fn decode_bytes(bytes: &[u8], model: CpuModel) -> Result<String, DecodeError> {
+    decode_for_model(Cursor::new(bytes), model)
 }
 
-pub fn decode(mut reader: BufReader<File>) -> String {
+/// Decodes until the reader runs out, stopping at the first instruction that can't be decoded.
+pub fn decode_for_model(reader: impl ByteCursor, model: CpuModel) -> Result<String, DecodeError> {
+    write_listing(reader, model, false)
+}
+
+/// Decodes like [`decode_for_model`], but writes each byte that doesn't start a decodable
+/// instruction as `db` and resumes at the byte after it, so data mixed in with the code still
+/// reassembles to the same binary. Instructions that wouldn't assemble back to the bytes they
+/// were decoded from are written as `db` too. Only a reader that fails for a reason other than
+/// running out stops it.
+pub fn decode_with_data(reader: impl ByteCursor, model: CpuModel) -> Result<String, DecodeError> {
+    write_listing(reader, model, true)
+}
+
+fn write_listing(
+    mut reader: impl ByteCursor,
+    model: CpuModel,
+    emits_data: bool,
+) -> Result<String, DecodeError> {
     let mut output = BufWriter::new(Vec::new());
 
-    output.write_all("bits 16\n\n".as_bytes()).unwrap();
+    // Keeps the assembler from accepting anything the model couldn't have executed.
+    let header = match model {
+        CpuModel::Intel8086 => "bits 16\n",
+        CpuModel::Intel80186 => "bits 16\ncpu 186\n",
+        CpuModel::Intel80386 => "bits 16\ncpu 386\n",
+    };
 
-    loop {
-        let Ok(instruction_byte) = reader.read_u8() else {
-            break;
-        };
+    output.write_all(format!("{header}\n").as_bytes()).unwrap();
+
+    while let Ok(instruction_byte) = reader.read_u8() {
+        match Instructions::read(&mut reader, instruction_byte, model) {
+            // Aliases of other opcodes, prefixes without an effect or in an unusual order and
+            // the forms nasm doesn't pick read back as other bytes, so they are kept as data.
+            Ok(instruction)
+                if emits_data
+                    && assemble(&format!("{header}{instruction}\n")).as_deref()
+                        != Ok(instruction.encoding().bytes()) =>
+            {
+                let bytes = instruction
+                    .encoding()
+                    .bytes()
+                    .iter()
+                    .map(|byte| format!("0x{byte:02x}"))
+                    .collect::<Vec<_>>();
 
-        let instruction = Instructions::read(&mut reader, instruction_byte);
+                output
+                    .write_all(format!("db {}\n", bytes.join(", ")).as_bytes())
+                    .unwrap();
+            }
+            Ok(instruction) => output
+                .write_all(format!("{instruction}\n").as_bytes())
+                .unwrap(),
+            Err(error) if emits_data && !matches!(error.reason, DecodeErrorReason::Io(_)) => {
+                output
+                    .write_all(format!("db 0x{instruction_byte:02x}\n").as_bytes())
+                    .unwrap();
 
-        output
-            .write_all(format!("{instruction}\n").as_bytes())
-            .unwrap();
+                reader
+                    .seek(SeekFrom::Start(error.offset + 1))
+                    .map_err(|io_error| DecodeError {
+                        reason: io_error.into(),
+                        ..error
+                    })?;
+            }
+            Err(error) => return Err(error),
+        }
     }
 
     output.flush().unwrap();
 
     let bytes = output.into_inner().unwrap();
-    String::from_utf8(bytes).unwrap()
+    Ok(String::from_utf8(bytes).unwrap())
 }
@@ -28,5 +28,8 @@ fn main() {
     let input = File::open(args[1].clone()).expect("Failed to open file");
     let mut reader = BufReader::new(input);
 
-    simulate(reader, store);
+    if let Err(error) = simulate(reader, store) {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
 }
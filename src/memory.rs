@@ -1,8 +1,12 @@
+use crate::cursor::ByteCursor;
 use crate::cycle::EstimatedCycleCount;
+use crate::error::{DecodeErrorReason, DecodeResult};
 use crate::instructions::operands::ImmediateValue;
+use crate::instructions::prefix::Prefixes;
 use crate::mode::InstructionMode;
 use crate::prelude::*;
 use crate::register::Register;
+use crate::segment_register::SegmentRegister;
 use crate::*;
 use byteorder::LittleEndian;
 use std::fmt::{Display, Formatter};
@@ -16,6 +20,25 @@ pub enum EffectiveAddress {
     RegisterSumPlusByte(Register, Register, SignedByte),
     RegisterSumPlusWord(Register, Register, SignedWord),
     DirectAddress(Word),
+    /// The 80386 forms selected by the address size prefix, which can scale an index and take
+    /// 32-bit displacements.
+    Extended(ExtendedAddress),
+}
+
+/// How a displacement was encoded, which the assembler can't always tell from its value.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Displacement {
+    None,
+    Byte(SignedByte),
+    DoubleWord(i32),
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct ExtendedAddress {
+    pub base: Option<Register>,
+    /// The index register and the factor it is scaled by.
+    pub index: Option<(Register, u8)>,
+    pub displacement: Displacement,
 }
 
 impl EstimatedCycleCount for EffectiveAddress {
@@ -48,16 +71,39 @@ impl EstimatedCycleCount for EffectiveAddress {
                 }
             }
             EffectiveAddress::DirectAddress(_) => 6,
+            // The 80386 computes addresses without spending extra cycles on them.
+            EffectiveAddress::Extended(_) => 0,
         }
     }
 }
 
 impl EffectiveAddress {
-    pub fn read(reader: &mut BufReader<File>, mode: InstructionMode, mem_byte: Byte) -> Self {
+    /// Addresses based on `bp` refer to the stack segment, everything else to the data segment.
+    pub fn default_segment(&self) -> SegmentRegister {
+        match self {
+            EffectiveAddress::Register(Register::Bp)
+            | EffectiveAddress::RegisterSum(Register::Bp, _)
+            | EffectiveAddress::RegisterPlusByte(Register::Bp, _)
+            | EffectiveAddress::RegisterPlusWord(Register::Bp, _)
+            | EffectiveAddress::RegisterSumPlusByte(Register::Bp, _, _)
+            | EffectiveAddress::RegisterSumPlusWord(Register::Bp, _, _)
+            | EffectiveAddress::Extended(ExtendedAddress {
+                base: Some(Register::Ebp | Register::Esp),
+                ..
+            }) => SegmentRegister::Ss,
+            _ => SegmentRegister::Ds,
+        }
+    }
+
+    pub fn read(
+        reader: &mut impl ByteCursor,
+        mode: InstructionMode,
+        mem_byte: Byte,
+    ) -> DecodeResult<Self> {
         use register::Register::*;
         use EffectiveAddress::*;
 
-        match mode {
+        Ok(match mode {
             InstructionMode::Memory => match mem_byte {
                 0b000 => RegisterSum(Bx, Si),
                 0b001 => RegisterSum(Bx, Di),
@@ -66,7 +112,7 @@ impl EffectiveAddress {
                 0b100 => Register(Si),
                 0b101 => Register(Di),
                 0b110 => {
-                    let displacement = reader.read_u16::<LittleEndian>().unwrap();
+                    let displacement = reader.read_u16::<LittleEndian>()?;
 
                     DirectAddress(displacement)
                 }
@@ -74,7 +120,7 @@ impl EffectiveAddress {
                 _ => panic!("Unable to compute effective address"),
             },
             InstructionMode::MemoryPlusByte => {
-                let displacement = reader.read_i8().unwrap();
+                let displacement = reader.read_i8()?;
                 if displacement == 0 {
                     match mem_byte {
                         0b000 => RegisterSum(Bx, Si),
@@ -102,7 +148,7 @@ impl EffectiveAddress {
                 }
             }
             InstructionMode::MemoryPlusWord => {
-                let displacement = reader.read_i16::<LittleEndian>().unwrap();
+                let displacement = reader.read_i16::<LittleEndian>()?;
                 if displacement == 0 {
                     match mem_byte {
                         0b000 => RegisterSum(Bx, Si),
@@ -129,10 +175,250 @@ impl EffectiveAddress {
                     }
                 }
             }
-            InstructionMode::Register => {
-                panic!("Unable to compute effective address for register-register mode")
+            InstructionMode::Register => return Err(DecodeErrorReason::InvalidModRm),
+        })
+    }
+
+    /// Reads the address of the accumulator moves, which the address size prefix widens to 32 bits.
+    pub fn read_direct(reader: &mut impl ByteCursor, prefixes: Prefixes) -> DecodeResult<Self> {
+        Ok(if prefixes.address_size_override {
+            EffectiveAddress::Extended(ExtendedAddress {
+                base: None,
+                index: None,
+                displacement: Displacement::DoubleWord(reader.read_i32::<LittleEndian>()?),
+            })
+        } else {
+            EffectiveAddress::DirectAddress(reader.read_u16::<LittleEndian>()?)
+        })
+    }
+
+    /// Reads the 32-bit form of the ModRM memory operand, along with the SIB byte an r/m of
+    /// 0b100 introduces.
+    pub fn read_extended(
+        reader: &mut impl ByteCursor,
+        mode: InstructionMode,
+        mem_byte: Byte,
+    ) -> DecodeResult<Self> {
+        let register = |bits: Byte| Register::from((bits << 1) + 1).extended();
+
+        let (base, index) = if mem_byte == 0b100 {
+            let sib = reader.read_u8()?;
+
+            let scale = 1 << (sib >> 6);
+            let index_bits = (0b00_111_000 & sib) >> 3;
+            let base_bits = 0b00_000_111 & sib;
+
+            // An index of esp stands for no index at all.
+            let index = (index_bits != 0b100).then(|| (register(index_bits), scale));
+            let base = (base_bits != 0b101 || mode != InstructionMode::Memory)
+                .then(|| register(base_bits));
+
+            (base, index)
+        } else if mem_byte == 0b101 && mode == InstructionMode::Memory {
+            (None, None)
+        } else {
+            (Some(register(mem_byte)), None)
+        };
+
+        let displacement = match mode {
+            InstructionMode::Memory if base.is_none() => {
+                Displacement::DoubleWord(reader.read_i32::<LittleEndian>()?)
             }
+            InstructionMode::Memory => Displacement::None,
+            InstructionMode::MemoryPlusByte => Displacement::Byte(reader.read_i8()?),
+            InstructionMode::MemoryPlusWord => {
+                Displacement::DoubleWord(reader.read_i32::<LittleEndian>()?)
+            }
+            InstructionMode::Register => return Err(DecodeErrorReason::InvalidModRm),
+        };
+
+        Ok(EffectiveAddress::Extended(ExtendedAddress {
+            base,
+            index,
+            displacement,
+        }))
+    }
+}
+
+/// One way to encode a memory operand: the mod and r/m fields of the ModRM byte, followed by
+/// the SIB byte and displacement.
+pub(crate) struct AddressEncoding {
+    pub mode: Byte,
+    pub register_or_memory: Byte,
+    pub bytes: Vec<Byte>,
+}
+
+impl EffectiveAddress {
+    /// Every way to encode the address, from the shortest displacement up. 16-bit addresses
+    /// can't be encoded under an address size prefix, and 32-bit ones only can.
+    pub(crate) fn encodings(&self, prefixes: Prefixes) -> Vec<AddressEncoding> {
+        use register::Register::*;
+        use EffectiveAddress::*;
+
+        let (base, index, displacement) = match *self {
+            Extended(address) if prefixes.address_size_override => {
+                return address.encoding().into_iter().collect();
+            }
+            _ if prefixes.address_size_override => return Vec::new(),
+            Extended(_) => return Vec::new(),
+            DirectAddress(address) => {
+                return vec![AddressEncoding {
+                    mode: 0b00,
+                    register_or_memory: 0b110,
+                    bytes: address.to_le_bytes().to_vec(),
+                }]
+            }
+            Register(base) => (base, None, 0),
+            RegisterSum(base, index) => (base, Some(index), 0),
+            RegisterPlusByte(base, displacement) => (base, None, i16::from(displacement)),
+            RegisterPlusWord(base, displacement) => (base, None, displacement),
+            RegisterSumPlusByte(base, index, displacement) => {
+                (base, Some(index), i16::from(displacement))
+            }
+            RegisterSumPlusWord(base, index, displacement) => (base, Some(index), displacement),
+        };
+
+        let register_or_memory = match (base, index) {
+            (Bx, Some(Si)) | (Si, Some(Bx)) => 0b000,
+            (Bx, Some(Di)) | (Di, Some(Bx)) => 0b001,
+            (Bp, Some(Si)) | (Si, Some(Bp)) => 0b010,
+            (Bp, Some(Di)) | (Di, Some(Bp)) => 0b011,
+            (Si, None) => 0b100,
+            (Di, None) => 0b101,
+            (Bp, None) => 0b110,
+            (Bx, None) => 0b111,
+            _ => return Vec::new(),
+        };
+
+        let mut encodings = Vec::new();
+
+        // A mode of 0b00 with an r/m of 0b110 stands for a direct address instead of [bp].
+        if displacement == 0 && register_or_memory != 0b110 {
+            encodings.push(AddressEncoding {
+                mode: 0b00,
+                register_or_memory,
+                bytes: Vec::new(),
+            });
         }
+
+        if let Ok(displacement) = i8::try_from(displacement) {
+            encodings.push(AddressEncoding {
+                mode: 0b01,
+                register_or_memory,
+                bytes: displacement.to_le_bytes().to_vec(),
+            });
+        }
+
+        encodings.push(AddressEncoding {
+            mode: 0b10,
+            register_or_memory,
+            bytes: displacement.to_le_bytes().to_vec(),
+        });
+
+        encodings
+    }
+}
+
+impl ExtendedAddress {
+    /// The encoding the displacement spells out, with a SIB byte when there is an index or the
+    /// base is esp.
+    fn encoding(&self) -> Option<AddressEncoding> {
+        let code = |register: Register| register.is_extended().then(|| register.code());
+
+        let (mode, mut displacement) = match (self.base, self.displacement) {
+            (None, Displacement::DoubleWord(value)) => (0b00, value.to_le_bytes().to_vec()),
+            (None, _) => return None,
+            // Both an r/m and a SIB base of 0b101 with a mode of 0b00 stand for no base.
+            (Some(Register::Ebp), Displacement::None) => return None,
+            (Some(_), Displacement::None) => (0b00, Vec::new()),
+            (Some(_), Displacement::Byte(value)) => (0b01, value.to_le_bytes().to_vec()),
+            (Some(_), Displacement::DoubleWord(value)) => (0b10, value.to_le_bytes().to_vec()),
+        };
+
+        if self.index.is_none() && self.base != Some(Register::Esp) {
+            return Some(AddressEncoding {
+                mode,
+                register_or_memory: self.base.map_or(Some(0b101), code)?,
+                bytes: displacement,
+            });
+        }
+
+        let (index, scale) = match self.index {
+            // An index of esp stands for no index at all.
+            Some((Register::Esp, _)) => return None,
+            Some((index, scale)) => (code(index)?, scale),
+            None => (0b100, 1),
+        };
+        let scale = match scale {
+            1 => 0b00,
+            2 => 0b01,
+            4 => 0b10,
+            8 => 0b11,
+            _ => return None,
+        };
+        let base = self.base.map_or(Some(0b101), code)?;
+
+        let mut bytes = vec![scale << 6 | index << 3 | base];
+        bytes.append(&mut displacement);
+
+        Some(AddressEncoding {
+            mode,
+            register_or_memory: 0b100,
+            bytes,
+        })
+    }
+}
+
+impl Display for ExtendedAddress {
+    /// Spells out whatever nasm would otherwise encode differently: displacements shorter or
+    /// longer than their value needs, and indices without a base, which it would split into one.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[")?;
+
+        let value = match self.displacement {
+            Displacement::None => 0,
+            Displacement::Byte(value) => i32::from(value),
+            Displacement::DoubleWord(value) => value,
+        };
+        let is_ebp_based = self.base == Some(Register::Ebp);
+
+        match self.displacement {
+            Displacement::Byte(0) if !is_ebp_based => f.write_str("byte ")?,
+            Displacement::DoubleWord(value)
+                if self.base.is_some() && i8::try_from(value).is_ok() =>
+            {
+                f.write_str("dword ")?
+            }
+            _ => {}
+        }
+
+        if self.base.is_none() && self.index.is_some() {
+            f.write_str("nosplit ")?;
+        }
+
+        let mut terms = Vec::new();
+
+        if let Some(base) = self.base {
+            terms.push(base.to_string());
+        }
+
+        // An unscaled index alone would read back as a base.
+        match self.index {
+            Some((index, 1)) if self.base.is_some() => terms.push(index.to_string()),
+            Some((index, scale)) => terms.push(format!("{index}*{scale}")),
+            None => {}
+        }
+
+        f.write_str(&terms.join(" + "))?;
+
+        if terms.is_empty() {
+            f.write_fmt(format_args!("dword {}", value as u32))?;
+        } else if !matches!(self.displacement, Displacement::None) {
+            let sign = if value.is_negative() { "-" } else { "+" };
+            f.write_fmt(format_args!(" {sign} {}", value.unsigned_abs()))?;
+        }
+
+        f.write_str("]")
     }
 }
 
@@ -145,34 +431,35 @@ impl Display for EffectiveAddress {
             }
             EffectiveAddress::RegisterPlusByte(register, byte) => {
                 let sign = if byte.is_negative() { "-" } else { "+" };
-                f.write_fmt(format_args!("[{register} {sign} {}]", byte.abs()))
+                f.write_fmt(format_args!("[{register} {sign} {}]", byte.unsigned_abs()))
             }
             EffectiveAddress::RegisterPlusWord(register, word) => {
                 let sign = if word.is_negative() { "-" } else { "+" };
-                f.write_fmt(format_args!("[{register} {sign} {}]", word.abs()))
+                f.write_fmt(format_args!("[{register} {sign} {}]", word.unsigned_abs()))
             }
             EffectiveAddress::RegisterSumPlusByte(register1, register2, byte) => {
                 let sign = if byte.is_negative() { "-" } else { "+" };
                 f.write_fmt(format_args!(
                     "[{register1} + {register2} {sign} {}]",
-                    byte.abs()
+                    byte.unsigned_abs()
                 ))
             }
             EffectiveAddress::RegisterSumPlusWord(register1, register2, word) => {
                 let sign = if word.is_negative() { "-" } else { "+" };
                 f.write_fmt(format_args!(
                     "[{register1} + {register2} {sign} {}]",
-                    word.abs()
+                    word.unsigned_abs()
                 ))
             }
             EffectiveAddress::DirectAddress(word) => f.write_fmt(format_args!("[{word}]")),
+            EffectiveAddress::Extended(address) => address.fmt(f),
         }
     }
 }
 
 pub trait Memory<T: Sized, const MEMORY_SIZE: usize> {
-    fn verify_address(&self, address: u16) {
-        assert!(address <= MEMORY_SIZE as u16);
+    fn verify_address(&self, address: u32) {
+        assert!((address as usize) < MEMORY_SIZE);
     }
 
     fn get_memory_mut(&mut self) -> &mut [T; MEMORY_SIZE];
@@ -184,19 +471,19 @@ pub trait ByteMemory<const MEMORY_SIZE: usize>
 where
     Self: Memory<u8, MEMORY_SIZE>,
 {
-    fn read_byte(&self, address: u16) -> u8 {
+    fn read_byte(&self, address: u32) -> u8 {
         self.verify_address(address);
 
         self.get_memory()[address as usize]
     }
 
-    fn read_signed_byte(&self, address: u16) -> i8 {
+    fn read_signed_byte(&self, address: u32) -> i8 {
         self.verify_address(address);
 
         self.get_memory()[address as usize] as i8
     }
 
-    fn read_word(&self, address: u16) -> u16 {
+    fn read_word(&self, address: u32) -> u16 {
         self.verify_address(address);
 
         let low_byte_address = address + 1;
@@ -211,7 +498,7 @@ where
         (u16::from(high) << 8) + u16::from(low)
     }
 
-    fn read_signed_word(&self, address: u16) -> i16 {
+    fn read_signed_word(&self, address: u32) -> i16 {
         self.verify_address(address);
 
         let low_byte_address = address + 1;
@@ -226,13 +513,13 @@ where
         (i16::from(high) << 8) + i16::from(low)
     }
 
-    fn write_byte(&mut self, address: u16, value: u8) {
+    fn write_byte(&mut self, address: u32, value: u8) {
         self.verify_address(address);
 
         self.get_memory_mut()[address as usize] = value;
     }
 
-    fn write_word(&mut self, address: u16, value: u16) {
+    fn write_word(&mut self, address: u32, value: u16) {
         self.verify_address(address);
 
         let low_byte_address = address + 1;
@@ -252,19 +539,19 @@ impl<T, const MEMORY_SIZE: usize> ByteMemory<MEMORY_SIZE> for T
 where
     T: Memory<u8, MEMORY_SIZE>,
 {
-    fn read_byte(&self, address: u16) -> u8 {
+    fn read_byte(&self, address: u32) -> u8 {
         self.verify_address(address);
 
         self.get_memory()[address as usize]
     }
 
-    fn read_signed_byte(&self, address: u16) -> i8 {
+    fn read_signed_byte(&self, address: u32) -> i8 {
         self.verify_address(address);
 
         self.get_memory()[address as usize] as i8
     }
 
-    fn read_word(&self, address: u16) -> u16 {
+    fn read_word(&self, address: u32) -> u16 {
         self.verify_address(address);
 
         let low_byte_address = address + 1;
@@ -279,7 +566,7 @@ where
         (u16::from(high) << 8) + u16::from(low)
     }
 
-    fn read_signed_word(&self, address: u16) -> i16 {
+    fn read_signed_word(&self, address: u32) -> i16 {
         self.verify_address(address);
 
         let low_byte_address = address + 1;
@@ -294,13 +581,13 @@ where
         (i16::from(high) << 8) + i16::from(low)
     }
 
-    fn write_byte(&mut self, address: u16, value: u8) {
+    fn write_byte(&mut self, address: u32, value: u8) {
         self.verify_address(address);
 
         self.get_memory_mut()[address as usize] = value;
     }
 
-    fn write_word(&mut self, address: u16, value: u16) {
+    fn write_word(&mut self, address: u32, value: u16) {
         self.verify_address(address);
 
         let low_byte_address = address + 1;
@@ -316,17 +603,20 @@ where
     }
 }
 
-const MAIN_MEMORY_SIZE: usize = u16::MAX as usize;
+const MAIN_MEMORY_SIZE: usize = 1 << 20;
 
 #[derive(Debug)]
 pub struct MemoryManager {
-    memory: [u8; MAIN_MEMORY_SIZE],
+    memory: Box<[u8; MAIN_MEMORY_SIZE]>,
 }
 
 impl Default for MemoryManager {
     fn default() -> Self {
         Self {
-            memory: [0b0; MAIN_MEMORY_SIZE],
+            memory: vec![0b0; MAIN_MEMORY_SIZE]
+                .into_boxed_slice()
+                .try_into()
+                .expect("Failed to allocate main memory"),
         }
     }
 }
@@ -376,54 +666,72 @@ impl MemoryManager {
                 address.value() as u16
             }
             EffectiveAddress::DirectAddress(address) => address,
+            EffectiveAddress::Extended(_) => {
+                panic!("32-bit addressing is only decoded, not simulated")
+            }
         }
     }
 
-    pub fn read_memory_from_effective_address(
+    pub fn physical_address(segment: u16, offset: u16) -> u32 {
+        ((u32::from(segment) << 4) + u32::from(offset)) & (MAIN_MEMORY_SIZE as u32 - 1)
+    }
+
+    /// Words are little-endian in main memory, like on the 8086, so that programs can address their
+    /// halves as bytes. A word at the end of a segment wraps around to its start.
+    pub fn read_from_memory(
         &self,
-        address: EffectiveAddress,
+        segment: u16,
+        offset: u16,
         is_wide: bool,
-        register_manager: &RegisterManager,
     ) -> (ImmediateValue, bool) {
-        let address = self.effective_address_to_address(address, register_manager);
+        let low = self.read_byte(Self::physical_address(segment, offset));
 
         if is_wide {
-            (self.read_signed_word(address).into(), address % 2 != 0)
+            let high = self.read_byte(Self::physical_address(segment, offset.wrapping_add(1)));
+
+            (
+                (i16::from_le_bytes([low, high])).into(),
+                !offset.is_multiple_of(2),
+            )
         } else {
-            (self.read_signed_byte(address).into(), false)
+            ((low as i8).into(), false)
         }
     }
 
-    pub fn write_to_effective_memory_address(
+    pub fn write_to_memory(
         &mut self,
-        address: EffectiveAddress,
+        segment: u16,
+        offset: u16,
         is_wide: bool,
-        register_manager: &RegisterManager,
         value: ImmediateValue,
     ) {
-        let address = self.effective_address_to_address(address, register_manager);
-
         if is_wide {
-            self.write_word(address, value.into())
+            let [low, high] = u16::from(value).to_le_bytes();
+
+            self.write_byte(Self::physical_address(segment, offset), low);
+            self.write_byte(
+                Self::physical_address(segment, offset.wrapping_add(1)),
+                high,
+            );
         } else {
             self.write_byte(
-                address,
+                Self::physical_address(segment, offset),
                 value.try_into().expect("Not is wide but value is word"),
             )
         }
     }
 
-    pub fn dump(&self) -> [u8; MAIN_MEMORY_SIZE] {
-        self.memory
+    pub fn dump(&self) -> Vec<u8> {
+        self.memory.to_vec()
     }
 }
 
 impl Memory<u8, MAIN_MEMORY_SIZE> for MemoryManager {
     fn get_memory_mut(&mut self) -> &mut [u8; MAIN_MEMORY_SIZE] {
-        &mut self.memory
+        self.memory.as_mut()
     }
 
     fn get_memory(&self) -> &[u8; MAIN_MEMORY_SIZE] {
-        &self.memory
+        self.memory.as_ref()
     }
 }
@@ -1,3 +1,4 @@
+use crate::instructions::prefix::Prefixes;
 use crate::prelude::*;
 use std::fmt::{Debug, Display, Formatter};
 
@@ -40,6 +41,14 @@ pub enum Register {
     Si,
     Bh,
     Di,
+    Eax,
+    Ecx,
+    Edx,
+    Ebx,
+    Esp,
+    Ebp,
+    Esi,
+    Edi,
 }
 
 use crate::instructions::operands::ImmediateValue;
@@ -89,11 +98,63 @@ impl Display for Register {
             Si => f.write_str("si"),
             Bh => f.write_str("bh"),
             Di => f.write_str("di"),
+            Eax => f.write_str("eax"),
+            Ecx => f.write_str("ecx"),
+            Edx => f.write_str("edx"),
+            Ebx => f.write_str("ebx"),
+            Esp => f.write_str("esp"),
+            Ebp => f.write_str("ebp"),
+            Esi => f.write_str("esi"),
+            Edi => f.write_str("edi"),
         }
     }
 }
 
 impl Register {
+    /// The 32-bit register whose low half is this word register, as named under an operand or
+    /// address size prefix.
+    pub fn extended(self) -> Self {
+        match self {
+            Ax | Eax => Eax,
+            Cx | Ecx => Ecx,
+            Dx | Edx => Edx,
+            Bx | Ebx => Ebx,
+            Sp | Esp => Esp,
+            Bp | Ebp => Ebp,
+            Si | Esi => Esi,
+            Di | Edi => Edi,
+            register => panic!("{register} has no 32-bit counterpart"),
+        }
+    }
+
+    /// The bits that select the register in an instruction, shared by its byte, word and double
+    /// word forms.
+    pub fn code(self) -> Byte {
+        match self {
+            Al | Ax | Eax => 0b000,
+            Cl | Cx | Ecx => 0b001,
+            Dl | Dx | Edx => 0b010,
+            Bl | Bx | Ebx => 0b011,
+            Ah | Sp | Esp => 0b100,
+            Ch | Bp | Ebp => 0b101,
+            Dh | Si | Esi => 0b110,
+            Bh | Di | Edi => 0b111,
+        }
+    }
+
+    /// Widens word registers to double words under an operand size prefix.
+    pub fn sized(self, is_wide: Wide, prefixes: Prefixes) -> Self {
+        if prefixes.is_double_word(is_wide) {
+            self.extended()
+        } else {
+            self
+        }
+    }
+
+    pub fn is_extended(self) -> bool {
+        matches!(self, Eax | Ecx | Edx | Ebx | Esp | Ebp | Esi | Edi)
+    }
+
     pub fn to_memory_address(self) -> u16 {
         match self {
             Ax | Ah => 0,
@@ -108,6 +169,9 @@ impl Register {
             Bp => 10,
             Si => 12,
             Di => 14,
+            Eax | Ecx | Edx | Ebx | Esp | Ebp | Esi | Edi => {
+                panic!("32-bit registers are only decoded, not simulated")
+            }
         }
     }
 
@@ -129,6 +193,7 @@ impl Register {
             Si => true,
             Bh => false,
             Di => true,
+            Eax | Ecx | Edx | Ebx | Esp | Ebp | Esi | Edi => true,
         }
     }
 }
@@ -194,23 +259,26 @@ impl RegisterManager {
             Di => ImmediateValue::SignedWord(i16::from_le_bytes(
                 self.read_word_from_register(register).to_le_bytes(),
             )),
+            Eax | Ecx | Edx | Ebx | Esp | Ebp | Esi | Edi => {
+                panic!("32-bit registers are only decoded, not simulated")
+            }
         }
     }
 
     pub fn read_byte_from_register(&self, register: Register) -> u8 {
-        self.read_byte(register.to_memory_address())
+        self.read_byte(register.to_memory_address().into())
     }
 
     pub fn read_word_from_register(&self, register: Register) -> u16 {
-        self.read_word(register.to_memory_address())
+        self.read_word(register.to_memory_address().into())
     }
 
     pub fn write_byte_to_register(&mut self, register: Register, value: u8) {
-        self.write_byte(register.to_memory_address(), value);
+        self.write_byte(register.to_memory_address().into(), value);
     }
 
     pub fn write_word_to_register(&mut self, register: Register, value: u16) {
-        self.write_word(register.to_memory_address(), value);
+        self.write_word(register.to_memory_address().into(), value);
     }
 
     pub fn register_memory_map(&self) -> Vec<(&str, i16)> {
@@ -24,7 +24,7 @@ impl Display for SegmentRegisterManager {
     }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum SegmentRegister {
     Es,
     Cs,
@@ -56,6 +56,16 @@ impl Display for SegmentRegister {
 }
 
 impl SegmentRegister {
+    /// The bits that select the register in an instruction, the reverse of [`From<Byte>`].
+    pub fn code(self) -> Byte {
+        match self {
+            Es => 0b00,
+            Cs => 0b01,
+            Ss => 0b10,
+            Ds => 0b11,
+        }
+    }
+
     pub fn to_memory_address(self) -> u16 {
         match self {
             Es => 0,
@@ -82,15 +92,15 @@ impl SegmentRegisterManager {
     }
 
     pub fn read_byte_from_segment_register(&self, segment_register: SegmentRegister) -> u8 {
-        self.read_byte(segment_register.to_memory_address())
+        self.read_byte(segment_register.to_memory_address().into())
     }
 
     pub fn read_word_from_segment_register(&self, segment_register: SegmentRegister) -> u16 {
-        self.read_word(segment_register.to_memory_address())
+        self.read_word(segment_register.to_memory_address().into())
     }
 
     pub fn write_byte_to_segment_register(&mut self, segment_register: SegmentRegister, value: u8) {
-        self.write_word(segment_register.to_memory_address(), value as u16);
+        self.write_word(segment_register.to_memory_address().into(), value as u16);
     }
 
     pub fn write_word_to_segment_register(
@@ -98,7 +108,7 @@ impl SegmentRegisterManager {
         segment_register: SegmentRegister,
         value: u16,
     ) {
-        self.write_word(segment_register.to_memory_address(), value);
+        self.write_word(segment_register.to_memory_address().into(), value);
     }
 
     pub fn segment_register_memory_map(&self) -> Vec<(&str, u16)> {
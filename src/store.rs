@@ -1,13 +1,54 @@
+use crate::cpu::CpuModel;
 use crate::instructions::operands::ImmediateValue;
-use crate::memory::{ByteMemory, EffectiveAddress};
+use crate::io_bus::{IoBus, RecordingIoBus};
+use crate::memory::EffectiveAddress;
+use crate::register::Register;
+use crate::segment_register::SegmentRegister;
 use crate::{FlagRegisterManager, MemoryManager, RegisterManager, SegmentRegisterManager};
+use std::any::Any;
 
-#[derive(Debug, Default)]
+/// Why `simulate` stopped before running off the end of the program.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StopReason {
+    /// A `ret` or `iret` was executed with every word the program pushed already popped, so that
+    /// it would pop the return address of whoever started the program rather than one the
+    /// program pushed itself. Returns that pop what a `push` or `call` left, like a `push` and
+    /// `ret` dispatching to an address, go on running the program.
+    Returned,
+    /// An interrupt was raised whose vector table entry is still null.
+    UnhandledInterrupt(u8),
+    /// A `hlt` was executed.
+    Halted,
+}
+
+#[derive(Debug)]
 pub struct Store {
     register_store: RegisterManager,
     memory_store: MemoryManager,
     segment_register_store: SegmentRegisterManager,
     flag_register_store: FlagRegisterManager,
+    segment_override: Option<SegmentRegister>,
+    /// Where SP was before the first of the words the program has pushed and not popped yet.
+    stack_base: Option<u16>,
+    stop_reason: Option<StopReason>,
+    io_bus: Box<dyn IoBus>,
+    cpu_model: CpuModel,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self {
+            register_store: RegisterManager::default(),
+            memory_store: MemoryManager::default(),
+            segment_register_store: SegmentRegisterManager::default(),
+            flag_register_store: FlagRegisterManager::default(),
+            segment_override: None,
+            stack_base: None,
+            stop_reason: None,
+            io_bus: Box::new(RecordingIoBus::default()),
+            cpu_model: CpuModel::default(),
+        }
+    }
 }
 
 impl Store {
@@ -43,23 +84,172 @@ impl Store {
         &mut self.flag_register_store
     }
 
+    /// Replaces the bus `in` and `out` instructions go through.
+    pub fn attach_io_bus(&mut self, io_bus: impl IoBus) {
+        self.io_bus = Box::new(io_bus);
+    }
+
+    /// The attached bus, if it is a `T`.
+    pub fn io_bus<T: IoBus>(&self) -> Option<&T> {
+        (self.io_bus.as_ref() as &dyn Any).downcast_ref()
+    }
+
+    pub fn io_bus_mut(&mut self) -> &mut dyn IoBus {
+        self.io_bus.as_mut()
+    }
+
+    pub fn cpu_model(&self) -> CpuModel {
+        self.cpu_model
+    }
+
+    /// Picks the processor `simulate` decodes and executes the program for.
+    pub fn set_cpu_model(&mut self, cpu_model: CpuModel) {
+        self.cpu_model = cpu_model;
+    }
+
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    pub fn stop(&mut self, reason: StopReason) {
+        self.stop_reason = Some(reason);
+    }
+
+    /// Whether a return would pop the return address of whoever started the program, rather
+    /// than a word the program pushed itself.
+    pub fn is_outermost_return(&self) -> bool {
+        self.is_above_stack_base()
+    }
+
+    /// Whether SP is at or above the bottom of the words the program pushed itself, so that
+    /// they have all been popped or released again, wherever the program put its stack.
+    fn is_above_stack_base(&self) -> bool {
+        let sp = self.register_store.read_word_from_register(Register::Sp);
+
+        self.stack_base
+            .is_none_or(|stack_base| stack_base.wrapping_sub(sp) as i16 <= 0)
+    }
+
+    pub fn push_word(&mut self, value: u16) {
+        if self.is_above_stack_base() {
+            self.stack_base = Some(self.register_store.read_word_from_register(Register::Sp));
+        }
+
+        let sp = self
+            .register_store
+            .read_word_from_register(Register::Sp)
+            .wrapping_sub(2);
+
+        self.register_store.write_word_to_register(Register::Sp, sp);
+        self.write_to_memory(
+            SegmentRegister::Ss,
+            sp,
+            true,
+            ImmediateValue::SignedWord(value as i16),
+        );
+    }
+
+    /// Pops a word off the stack, also returning whether it was read from an unaligned address.
+    pub fn pop_word(&mut self) -> (u16, bool) {
+        let sp = self.register_store.read_word_from_register(Register::Sp);
+        let (value, clock_penalty) = self.read_from_memory(SegmentRegister::Ss, sp, true);
+
+        self.register_store
+            .write_word_to_register(Register::Sp, sp.wrapping_add(2));
+
+        if self.is_above_stack_base() {
+            self.stack_base = None;
+        }
+
+        (value.into(), clock_penalty)
+    }
+
+    pub fn set_segment_override(&mut self, segment_override: Option<SegmentRegister>) {
+        self.segment_override = segment_override;
+    }
+
+    /// Picks the segment a memory operand refers to, honouring any segment override prefix
+    /// on the instruction currently being executed.
+    fn resolve_segment(&self, address: EffectiveAddress) -> u16 {
+        let segment = self
+            .segment_override
+            .unwrap_or_else(|| address.default_segment());
+
+        self.segment_register_store.read_value(segment)
+    }
+
+    pub fn read_from_memory(
+        &self,
+        segment: SegmentRegister,
+        offset: u16,
+        is_wide: bool,
+    ) -> (ImmediateValue, bool) {
+        let segment = self.segment_register_store.read_value(segment);
+
+        self.memory_store.read_from_memory(segment, offset, is_wide)
+    }
+
+    pub fn write_to_memory(
+        &mut self,
+        segment: SegmentRegister,
+        offset: u16,
+        is_wide: bool,
+        value: ImmediateValue,
+    ) {
+        let segment = self.segment_register_store.read_value(segment);
+
+        self.memory_store
+            .write_to_memory(segment, offset, is_wide, value)
+    }
+
+    pub fn read_from_effective_memory_address(
+        &self,
+        address: EffectiveAddress,
+        is_wide: bool,
+    ) -> (ImmediateValue, bool) {
+        let segment = self.resolve_segment(address);
+        let offset = self
+            .memory_store
+            .effective_address_to_address(address, &self.register_store);
+
+        self.memory_store.read_from_memory(segment, offset, is_wide)
+    }
+
+    /// Reads a `segment:offset` pair stored offset first, also returning whether either word was
+    /// unaligned.
+    pub fn read_far_pointer_from_effective_memory_address(
+        &self,
+        address: EffectiveAddress,
+    ) -> ((u16, u16), bool) {
+        let segment = self.resolve_segment(address);
+        let offset = self
+            .memory_store
+            .effective_address_to_address(address, &self.register_store);
+
+        let (pointer_offset, clock_penalty) =
+            self.memory_store.read_from_memory(segment, offset, true);
+        let (pointer_segment, _) =
+            self.memory_store
+                .read_from_memory(segment, offset.wrapping_add(2), true);
+
+        (
+            (pointer_segment.into(), pointer_offset.into()),
+            clock_penalty,
+        )
+    }
+
     pub fn write_to_effective_memory_address(
         &mut self,
         address: EffectiveAddress,
         is_wide: bool,
         value: ImmediateValue,
     ) {
-        let register_store = &self.register_store;
-        let memory_store = &mut self.memory_store;
-        let address = memory_store.effective_address_to_address(address, register_store);
-
-        if is_wide {
-            memory_store.write_word(address, value.into())
-        } else {
-            memory_store.write_byte(
-                address,
-                value.try_into().expect("Not is wide but value is word"),
-            )
-        }
+        let segment = self.resolve_segment(address);
+        let offset = self
+            .memory_store
+            .effective_address_to_address(address, &self.register_store);
+
+        self.memory_store
+            .write_to_memory(segment, offset, is_wide, value)
     }
 }
@@ -0,0 +1,90 @@
+use instruction_decoding_8086::assemble;
+use instruction_decoding_8086::error::{AssembleError, AssembleErrorReason};
+
+#[test]
+fn branches_to_labels_take_the_shortest_form_that_reaches() {
+    let mut source = String::from("bits 16\n\njmp far_end\n");
+    source.push_str(&"nop\n".repeat(200));
+    source.push_str("far_end:\njnz far_end\nloop .back\n.back:\n");
+
+    let program = assemble(&source).unwrap();
+
+    // The forward jmp only turns out to be out of reach of the short form once the nops are
+    // laid out.
+    assert_eq!(program[..3], [0xe9, 0xc8, 0x00]);
+    assert_eq!(program[203..], [0x75, 0xfe, 0xe2, 0x00]);
+}
+
+#[test]
+fn local_labels_belong_to_the_label_before_them() {
+    let source = "first:\n.loop:\ndec cx\njnz .loop\nsecond:\n.loop:\njmp .loop\njmp first.loop\n";
+
+    assert_eq!(
+        assemble(source).unwrap(),
+        [0x49, 0x75, 0xfd, 0xeb, 0xfe, 0xeb, 0xf9]
+    );
+}
+
+#[test]
+fn size_keywords_pick_the_encoding() {
+    let source = "\
+push 5
+push word 5
+o32 push byte -2
+jmp $+3
+jmp near $+3
+mov al, [ebx]
+mov al, [byte ebx + 0]
+mov al, [dword ebx + 0]
+add word [bx], 7
+";
+
+    assert_eq!(
+        assemble(source).unwrap(),
+        [
+            0x6a, 0x05, //
+            0x68, 0x05, 0x00, //
+            0x66, 0x6a, 0xfe, //
+            0xeb, 0x01, //
+            0xe9, 0x00, 0x00, //
+            0x67, 0x8a, 0x03, //
+            0x67, 0x8a, 0x43, 0x00, //
+            0x67, 0x8a, 0x83, 0x00, 0x00, 0x00, 0x00, //
+            0x83, 0x07, 0x07,
+        ]
+    );
+}
+
+#[test]
+fn assemble_reports_the_line_and_why_it_failed() {
+    let error = |source| assemble(source).unwrap_err();
+
+    assert_eq!(
+        error("bits 16\n\nmov [bx], 5\n"),
+        AssembleError {
+            line: 3,
+            reason: AssembleErrorReason::UnknownSize,
+        }
+    );
+    assert_eq!(
+        error("jmp nowhere\n").reason,
+        AssembleErrorReason::UndefinedLabel("nowhere".to_string())
+    );
+    assert_eq!(
+        error("cpu 8086\npush 5\n").reason,
+        AssembleErrorReason::UnsupportedByModel
+    );
+    assert_eq!(
+        error("mov al, 256\n").reason,
+        AssembleErrorReason::OutOfRange(256)
+    );
+    assert!(matches!(
+        error("mov [bx], [si]\n").reason,
+        AssembleErrorReason::Encode(_)
+    ));
+    // esc only takes a word register, and saying so mustn't need the encoding that failed.
+    assert_eq!(
+        error("esc 1, cl\n").to_string(),
+        "no encoding for esc 1, cl on line 1"
+    );
+}
@@ -1,56 +1,419 @@
-use std::env::temp_dir;
-use std::fs::{remove_file, File};
-use std::io::{BufReader, Read, Write};
+use std::fs::{read, read_to_string};
+use std::io::Cursor;
 use std::path::Path;
-use std::process::Command;
 
 #[macro_export]
 macro_rules! test_decode_listing {
     ($listing_name:ident) => {
+        test_decode_listing!(
+            $listing_name,
+            instruction_decoding_8086::cpu::CpuModel::Intel8086
+        );
+    };
+    ($listing_name:ident, $cpu_model:expr) => {
         #[test]
         fn $listing_name() {
-            let path = Path::new(file!())
-                .parent()
-                .unwrap()
-                .join(Path::new("./test_listings"))
-                .join(Path::new(stringify!($listing_name)));
-
-            let input = File::open(&path).unwrap_or_else(|_| panic!("Failed to open {path:?}"));
-            let reader = BufReader::new(input);
-            let output = instruction_decoding_8086::decode(reader);
-
-            let temp_file_path = path
-                .join(temp_dir())
-                .join(format!("{}.asm", stringify!($listing_name)));
-            let mut temp_file = File::create(&temp_file_path)
-                .unwrap_or_else(|_| panic!("Failed to create {path:?}"));
-            temp_file.write_all(output.as_bytes()).unwrap();
-
-            Command::new("nasm")
-                .arg(temp_file_path.to_str().unwrap())
-                .spawn()
-                .unwrap()
-                .wait()
-                .unwrap();
-
-            let mut input = File::open(&path).unwrap_or_else(|_| panic!("Failed to open {path:?}"));
-            let mut data = vec![];
-            input.read_to_end(&mut data).unwrap();
-
-            let mut temp_input = File::open(&temp_file_path.to_str().unwrap().replace(".asm", ""))
-                .unwrap_or_else(|_| panic!("Failed to open {path:?}"));
-            let mut generated_bin = vec![];
-            temp_input.read_to_end(&mut generated_bin).unwrap();
-
-            remove_file(temp_file_path).unwrap();
+            let data = read_listing(stringify!($listing_name));
+            let output = instruction_decoding_8086::decode_bytes(&data, $cpu_model).unwrap();
+
+            let generated_bin = instruction_decoding_8086::assemble(&output).unwrap();
 
             assert_eq!(generated_bin, data);
+            assert_eq!(reencode(&data, $cpu_model), data);
         }
     };
 }
 
+/// Decodes every instruction in `data` and encodes it back the way it was.
+fn reencode(data: &[u8], model: instruction_decoding_8086::cpu::CpuModel) -> Vec<u8> {
+    use byteorder::ReadBytesExt;
+    use instruction_decoding_8086::{EncodingChoice, Instructions};
+
+    let mut reader = Cursor::new(data);
+    let mut encoded = vec![];
+
+    while let Ok(value) = reader.read_u8() {
+        let instruction = Instructions::read(&mut reader, value, model).unwrap();
+        encoded.extend(instruction.encode(EncodingChoice::Original).unwrap());
+    }
+
+    encoded
+}
+
+/// Reads the reference binary of the listing `name` in `test_listings`, checking that
+/// assembling its source gives the same bytes.
+fn read_listing(name: &str) -> Vec<u8> {
+    let path = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join(Path::new("./test_listings"))
+        .join(Path::new(name));
+
+    let data = read(&path).unwrap_or_else(|_| panic!("Failed to open {path:?}"));
+    let source_path = path.with_extension("asm");
+    let source =
+        read_to_string(&source_path).unwrap_or_else(|_| panic!("Failed to open {source_path:?}"));
+    let assembled = instruction_decoding_8086::assemble(&source)
+        .unwrap_or_else(|error| panic!("Failed to assemble {source_path:?}: {error}"));
+
+    assert_eq!(assembled, data, "{source_path:?} assembles differently");
+
+    data
+}
+
 test_decode_listing!(listing_0037_single_register_mov);
 test_decode_listing!(listing_0038_many_register_mov);
 test_decode_listing!(listing_0039_more_movs);
 test_decode_listing!(listing_0040_challenge_movs);
 test_decode_listing!(listing_0041_add_sub_cmp_jnz);
+test_decode_listing!(listing_0042_completionist_decode);
+test_decode_listing!(listing_segment_overrides);
+test_decode_listing!(
+    listing_80186_extensions,
+    instruction_decoding_8086::cpu::CpuModel::Intel80186
+);
+test_decode_listing!(
+    listing_80386_prefixes,
+    instruction_decoding_8086::cpu::CpuModel::Intel80386
+);
+
+#[test]
+fn decode_bytes_matches_decoding_a_reader() {
+    let data = read_listing("listing_0042_completionist_decode");
+
+    let from_reader = instruction_decoding_8086::decode(Cursor::new(&data)).unwrap();
+    let from_bytes = instruction_decoding_8086::decode_bytes(
+        &data,
+        instruction_decoding_8086::cpu::CpuModel::Intel8086,
+    )
+    .unwrap();
+
+    assert_eq!(from_bytes, from_reader);
+}
+
+#[test]
+fn decode_reports_where_and_why_it_failed() {
+    use instruction_decoding_8086::cpu::CpuModel;
+    use instruction_decoding_8086::error::{DecodeError, DecodeErrorReason};
+
+    // mov ax, [bp + 4] missing its displacement.
+    assert_eq!(
+        instruction_decoding_8086::decode_bytes(&[0x90, 0x8b, 0x46], CpuModel::Intel8086),
+        Err(DecodeError {
+            offset: 1,
+            bytes: vec![0x8b, 0x46],
+            reason: DecodeErrorReason::TruncatedInstruction,
+        })
+    );
+
+    // pop cs, which the 80186 no longer decodes.
+    assert_eq!(
+        instruction_decoding_8086::decode_bytes(&[0x0f, 0x90], CpuModel::Intel80186),
+        Err(DecodeError {
+            offset: 0,
+            bytes: vec![0x0f],
+            reason: DecodeErrorReason::UnknownOpcode,
+        })
+    );
+
+    // call far dx, which has no far pointer to load.
+    assert_eq!(
+        instruction_decoding_8086::decode_bytes(&[0xff, 0xda], CpuModel::Intel8086),
+        Err(DecodeError {
+            offset: 0,
+            bytes: vec![0xff, 0xda],
+            reason: DecodeErrorReason::InvalidModRm,
+        })
+    );
+
+    // lea ax, cx, with the prefix counted as part of the instruction.
+    assert_eq!(
+        instruction_decoding_8086::decode_bytes(&[0x26, 0x8d, 0xc1], CpuModel::Intel8086),
+        Err(DecodeError {
+            offset: 0,
+            bytes: vec![0x26, 0x8d, 0xc1],
+            reason: DecodeErrorReason::InvalidModRm,
+        })
+    );
+}
+
+#[test]
+fn decode_prints_the_most_negative_displacements() {
+    use instruction_decoding_8086::cpu::CpuModel;
+
+    let cases: [(&[u8], &str); 2] = [
+        (&[0x00, 0x52, 0x80], "add [bp + si - 128], dl"),
+        (&[0x8b, 0x86, 0x00, 0x80], "mov ax, [bp - 32768]"),
+    ];
+
+    for (data, expected) in cases {
+        let output = instruction_decoding_8086::decode_bytes(data, CpuModel::Intel8086).unwrap();
+
+        assert!(output.contains(expected), "{output}");
+        assert_eq!(instruction_decoding_8086::assemble(&output).unwrap(), data);
+    }
+}
+
+#[test]
+fn decode_counts_prefixes_into_relative_branches() {
+    use instruction_decoding_8086::cpu::CpuModel;
+
+    let cases: [(&[u8], &str); 8] = [
+        (&[0xf0, 0x70, 0x94], "lock jo $-105"),
+        (&[0xf0, 0x72, 0x80], "lock jb $-125"),
+        (&[0x2e, 0x74, 0x10], "cs je $+19"),
+        (&[0x26, 0xe2, 0xfe], "es loop $+1"),
+        (&[0x67, 0xe2, 0xfe], "a32 loop $+1"),
+        (&[0x3e, 0xeb, 0x00], "ds jmp $+3"),
+        (&[0x2e, 0xe9, 0x00, 0x00], "cs jmp near $+4"),
+        (&[0x66, 0xe8, 0x10, 0x00, 0x00, 0x00], "call dword $+22"),
+    ];
+
+    for (data, expected) in cases {
+        let output = instruction_decoding_8086::decode_bytes(data, CpuModel::Intel80386).unwrap();
+
+        assert!(output.contains(expected), "{output}");
+        assert_eq!(instruction_decoding_8086::assemble(&output).unwrap(), data);
+    }
+}
+
+#[test]
+fn decode_keeps_the_segment_override_of_string_operations() {
+    use instruction_decoding_8086::cpu::CpuModel;
+
+    let cases: [(&[u8], &str); 3] = [
+        (&[0x26, 0xa4], "es movsb"),
+        (&[0x26, 0xd7], "es xlat"),
+        (&[0xf3, 0x2e, 0xad], "cs rep lodsw"),
+    ];
+
+    for (data, expected) in cases {
+        let output = instruction_decoding_8086::decode_bytes(data, CpuModel::Intel8086).unwrap();
+
+        assert!(output.contains(expected), "{output}");
+        assert_eq!(instruction_decoding_8086::assemble(&output).unwrap(), data);
+    }
+}
+
+#[test]
+fn decode_writes_esc_out_with_its_opcode_and_operand() {
+    use instruction_decoding_8086::cpu::CpuModel;
+
+    let cases: [(&[u8], &str); 3] = [
+        (&[0xd8, 0x07], "esc 0, [bx]"),
+        (&[0x26, 0xdf, 0x9e, 0x00, 0x80], "esc 59, es:[bp - 32768]"),
+        (&[0xdf, 0xf8], "esc 63, ax"),
+    ];
+
+    for (data, expected) in cases {
+        let output = instruction_decoding_8086::decode_bytes(data, CpuModel::Intel8086).unwrap();
+
+        assert!(output.contains(expected), "{output}");
+        assert_eq!(instruction_decoding_8086::assemble(&output).unwrap(), data);
+    }
+}
+
+#[test]
+fn decode_with_data_reassembles_around_undecodable_bytes() {
+    let listing = read_listing("listing_0041_add_sub_cmp_jnz");
+
+    // Bytes that decode, but that nasm would write differently: prefixes without an effect,
+    // repeated or out of nasm's order, and the 8086 aliases of jcc, ret, retf, shl and test.
+    let rewritten: [&[u8]; 10] = [
+        &[0xf2, 0x47],
+        &[0x26, 0x26, 0xa4],
+        &[0x2e, 0xf3, 0xa4],
+        &[0x60, 0x02],
+        &[0xc0, 0x02, 0x00],
+        &[0xc1],
+        &[0xc8, 0x04, 0x00],
+        &[0xc9],
+        &[0xd0, 0xf0],
+        &[0xf6, 0xc8, 0x05],
+    ];
+
+    // Unknown opcodes up front, and a mov cut short of its displacement at the end.
+    let data = [
+        &[0xd6, 0xf1][..],
+        &listing,
+        &rewritten.concat(),
+        // add ax, bx with the d bit set, then a segment override on push es.
+        &[0x03, 0xc3, 0x2e, 0x06],
+        &[0x8b, 0x46],
+    ]
+    .concat();
+
+    let output = instruction_decoding_8086::decode_with_data(
+        Cursor::new(&data),
+        instruction_decoding_8086::cpu::CpuModel::Intel8086,
+    )
+    .unwrap();
+
+    assert!(output.contains("db 0xd6\ndb 0xf1\n"));
+    assert!(output.contains("add bx, [bx + si]\n"));
+    assert!(output.contains("db 0x03, 0xc3\ncs push es\n"));
+
+    for bytes in rewritten {
+        let line = bytes
+            .iter()
+            .map(|byte| format!("0x{byte:02x}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        assert!(output.contains(&format!("db {line}\n")), "{output}");
+    }
+
+    assert_eq!(instruction_decoding_8086::assemble(&output).unwrap(), data);
+}
+
+#[test]
+fn decoded_instructions_carry_their_encoding() {
+    use byteorder::ReadBytesExt;
+    use instruction_decoding_8086::cpu::CpuModel;
+    use instruction_decoding_8086::Instructions;
+
+    // nop, then es: mov ax, [bp + 4]
+    let mut reader = Cursor::new([0x90, 0x26, 0x8b, 0x46, 0x04]);
+    let mut decoded = vec![];
+
+    while let Ok(value) = reader.read_u8() {
+        decoded.push(Instructions::read(&mut reader, value, CpuModel::Intel8086).unwrap());
+    }
+
+    let encodings = decoded
+        .iter()
+        .map(|instruction| {
+            let encoding = instruction.encoding();
+            (encoding.offset(), encoding.len(), encoding.bytes().to_vec())
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        encodings,
+        vec![(0, 1, vec![0x90]), (1, 4, vec![0x26, 0x8b, 0x46, 0x04])]
+    );
+    assert_eq!(decoded[1].encoding().end(), 5);
+}
+
+#[test]
+fn decoded_instructions_convert_to_the_generic_instruction() {
+    use byteorder::ReadBytesExt;
+    use instruction_decoding_8086::cpu::CpuModel;
+    use instruction_decoding_8086::{ImmediateValue, Instruction, Instructions, Op, Operand};
+
+    // add word [bx + 4], 7, with the immediate sign extended from a byte.
+    let mut reader = Cursor::new([0x83, 0x47, 0x04, 0x07]);
+    let value = reader.read_u8().unwrap();
+    let instruction =
+        Instruction::from(&Instructions::read(&mut reader, value, CpuModel::Intel8086).unwrap());
+
+    assert_eq!(instruction.op, Op::Add);
+    assert!(instruction.is_wide);
+    assert!(matches!(instruction.operands[0], Some(Operand::Memory(_))));
+    assert!(matches!(
+        instruction.operands[1],
+        Some(Operand::Immediate(ImmediateValue::SignedWord(7)))
+    ));
+    assert!(instruction.operands[2].is_none());
+    assert_eq!(instruction.encoding.bytes(), [0x83, 0x47, 0x04, 0x07]);
+    assert_eq!(instruction.to_string(), "add [bx + 4], word 7");
+}
+
+#[test]
+fn encoding_table_covers_every_8086_opcode() {
+    use instruction_decoding_8086::cpu::CpuModel;
+    use instruction_decoding_8086::ENCODINGS;
+    use std::collections::BTreeSet;
+
+    let covered = ENCODINGS
+        .iter()
+        .filter(|spec| spec.since == CpuModel::Intel8086)
+        .flat_map(|spec| spec.opcodes())
+        .collect::<BTreeSet<_>>();
+    let uncovered = (0..=u8::MAX)
+        .filter(|opcode| !covered.contains(opcode))
+        .collect::<Vec<_>>();
+
+    // The prefixes, the opcodes the 8086 runs as aliases of the conditional jumps and returns,
+    // and the two it leaves undefined.
+    let expected = [0x26, 0x2e, 0x36, 0x3e]
+        .into_iter()
+        .chain(0x60..=0x6f)
+        .chain([0xc0, 0xc1, 0xc8, 0xc9, 0xd6, 0xf0, 0xf1, 0xf2, 0xf3])
+        .collect::<Vec<_>>();
+
+    assert_eq!(uncovered, expected);
+}
+
+#[test]
+fn encoding_picks_the_shortest_or_the_original_form() {
+    use byteorder::ReadBytesExt;
+    use instruction_decoding_8086::cpu::CpuModel;
+    use instruction_decoding_8086::{EncodingChoice, Instructions};
+
+    let encode = |bytes: &[u8], choice| {
+        let mut reader = Cursor::new(bytes);
+        let value = reader.read_u8().unwrap();
+
+        Instructions::read(&mut reader, value, CpuModel::Intel8086)
+            .unwrap()
+            .encode(choice)
+            .unwrap()
+    };
+
+    let alternatives: [(&[u8], &[u8]); 4] = [
+        // add ax, 7 through the accumulator form, against the sign extended immediate.
+        (&[0x05, 0x07, 0x00], &[0x83, 0xc0, 0x07]),
+        // add ax, bx with the d bit set.
+        (&[0x03, 0xc3], &[0x01, 0xd8]),
+        // mov ax, [bx] with a zero displacement.
+        (&[0x8b, 0x47, 0x00], &[0x8b, 0x07]),
+        // jmp near $+3, which is in reach of the short form.
+        (&[0xe9, 0x00, 0x00], &[0xeb, 0x01]),
+    ];
+
+    for (original, shortest) in alternatives {
+        assert_eq!(encode(original, EncodingChoice::Original), original);
+        assert_eq!(encode(original, EncodingChoice::Shortest), shortest);
+    }
+}
+
+#[test]
+fn hand_built_instructions_encode() {
+    use instruction_decoding_8086::memory::EffectiveAddress;
+    use instruction_decoding_8086::register::Register;
+    use instruction_decoding_8086::{
+        Encoding, EncodingChoice, ImmediateValue, Instruction, Op, Operand, Prefixes,
+    };
+
+    let instruction = |op, operands: [Option<Operand>; 3]| Instruction {
+        op,
+        prefixes: Prefixes::default(),
+        operands,
+        is_wide: true,
+        encoding: Encoding::default(),
+    };
+
+    let mov = instruction(
+        Op::Mov,
+        [
+            Some(Operand::Register(Register::Cx)),
+            Some(Operand::Immediate(ImmediateValue::SignedWord(12))),
+            None,
+        ],
+    );
+    assert_eq!(
+        mov.encode(EncodingChoice::Original).unwrap(),
+        [0xb9, 0x0c, 0x00]
+    );
+
+    let memory_to_memory = instruction(
+        Op::Mov,
+        [
+            Some(Operand::Memory(EffectiveAddress::Register(Register::Bx))),
+            Some(Operand::Memory(EffectiveAddress::Register(Register::Si))),
+            None,
+        ],
+    );
+    assert!(memory_to_memory.encode(EncodingChoice::Shortest).is_err());
+}
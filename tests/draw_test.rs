@@ -1,21 +1,36 @@
-use std::fs::File;
-use std::io::{BufReader, Seek, Write};
+use std::fs::{read, read_to_string, File};
+use std::io::{Cursor, Write};
 use std::path::Path;
 
-#[test]
-fn listing_0054_draw_rectangle() {
-    let store = &mut instruction_decoding_8086::Store::default();
-
+/// Reads the reference binary of the listing `name` in `test_listings`, checking that
+/// assembling its source gives the same bytes.
+fn read_listing(name: &str) -> Vec<u8> {
     let path = Path::new(file!())
         .parent()
         .unwrap()
         .join(Path::new("./test_listings"))
-        .join(Path::new("listing_0054_draw_rectangle"));
+        .join(Path::new(name));
 
-    let input = File::open(&path).unwrap_or_else(|_| panic!("Failed to open {path:?}"));
+    let data = read(&path).unwrap_or_else(|_| panic!("Failed to open {path:?}"));
+    let source_path = path.with_extension("asm");
+    let source =
+        read_to_string(&source_path).unwrap_or_else(|_| panic!("Failed to open {source_path:?}"));
+    let assembled = instruction_decoding_8086::assemble(&source)
+        .unwrap_or_else(|error| panic!("Failed to assemble {source_path:?}: {error}"));
 
-    let mut reader = BufReader::new(input);
-    (reader, _) = instruction_decoding_8086::simulate(reader, store);
+    assert_eq!(assembled, data, "{source_path:?} assembles differently");
+
+    data
+}
+
+#[test]
+fn listing_0054_draw_rectangle() {
+    let store = &mut instruction_decoding_8086::Store::default();
+
+    let program = read_listing("listing_0054_draw_rectangle");
+
+    let reader = Cursor::new(program);
+    instruction_decoding_8086::simulate(reader, store).unwrap();
 
     File::create("listing_0054_draw_rectangle.dump")
         .unwrap()
@@ -27,16 +42,10 @@ fn listing_0054_draw_rectangle() {
 fn listing_0055_challenge_rectangle() {
     let store = &mut instruction_decoding_8086::Store::default();
 
-    let path = Path::new(file!())
-        .parent()
-        .unwrap()
-        .join(Path::new("./test_listings"))
-        .join(Path::new("listing_0055_challenge_rectangle"));
-
-    let input = File::open(&path).unwrap_or_else(|_| panic!("Failed to open {path:?}"));
+    let program = read_listing("listing_0055_challenge_rectangle");
 
-    let mut reader = BufReader::new(input);
-    (reader, _) = instruction_decoding_8086::simulate(reader, store);
+    let reader = Cursor::new(program);
+    instruction_decoding_8086::simulate(reader, store).unwrap();
 
     File::create("listing_0055_challenge_rectangle.dump")
         .unwrap()
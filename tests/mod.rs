@@ -1,2 +1,3 @@
+mod assemble_test;
 mod decode_test;
 mod simulation_test;
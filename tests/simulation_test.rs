@@ -1,25 +1,28 @@
-use std::fs::File;
-use std::io::{BufReader, Seek};
+use std::fs::{read, read_to_string};
+use std::io::{Cursor, Seek};
 use std::path::Path;
 
 #[macro_export]
 macro_rules! test_simulate_listing {
     ($listing_name:ident, $should_include_clock_cycles:literal) => {
+        test_simulate_listing!(
+            $listing_name,
+            $should_include_clock_cycles,
+            instruction_decoding_8086::cpu::CpuModel::Intel8086
+        );
+    };
+    ($listing_name:ident, $should_include_clock_cycles:literal, $cpu_model:expr) => {
         #[test]
+        #[allow(non_snake_case)]
         fn $listing_name() {
             let store = &mut instruction_decoding_8086::Store::default();
+            store.set_cpu_model($cpu_model);
 
-            let path = Path::new(file!())
-                .parent()
-                .unwrap()
-                .join(Path::new("./test_listings"))
-                .join(Path::new(stringify!($listing_name)));
-
-            let input = File::open(&path).unwrap_or_else(|_| panic!("Failed to open {path:?}"));
+            let program = read_listing(stringify!($listing_name));
 
-            let mut reader = BufReader::new(input);
+            let mut reader = Cursor::new(program);
             let (modded_reader, num_cpu_cycles) =
-                instruction_decoding_8086::simulate(reader, store);
+                instruction_decoding_8086::simulate(reader, store).unwrap();
             reader = modded_reader;
 
             insta::assert_debug_snapshot!(reader.stream_position());
@@ -36,6 +39,27 @@ macro_rules! test_simulate_listing {
     };
 }
 
+/// Reads the reference binary of the listing `name` in `test_listings`, checking that
+/// assembling its source gives the same bytes.
+fn read_listing(name: &str) -> Vec<u8> {
+    let path = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join(Path::new("./test_listings"))
+        .join(Path::new(name));
+
+    let data = read(&path).unwrap_or_else(|_| panic!("Failed to open {path:?}"));
+    let source_path = path.with_extension("asm");
+    let source =
+        read_to_string(&source_path).unwrap_or_else(|_| panic!("Failed to open {source_path:?}"));
+    let assembled = instruction_decoding_8086::assemble(&source)
+        .unwrap_or_else(|error| panic!("Failed to assemble {source_path:?}: {error}"));
+
+    assert_eq!(assembled, data, "{source_path:?} assembles differently");
+
+    data
+}
+
 test_simulate_listing!(listing_0043_immediate_movs, false);
 test_simulate_listing!(listing_0044_register_movs, false);
 test_simulate_listing!(listing_0045_challenge_register_movs, false);
@@ -49,3 +73,75 @@ test_simulate_listing!(listing_0052_memory_add_loop, false);
 test_simulate_listing!(listing_0053_add_loop_challenge, false);
 test_simulate_listing!(listing_0056_estimating_cycles, true);
 test_simulate_listing!(listing_0057_challenge_cycles, true);
+test_simulate_listing!(listing_0059_SingleScalar, true);
+test_simulate_listing!(listing_0060_Unroll2Scalar, true);
+test_simulate_listing!(listing_0061_DualScalar, true);
+test_simulate_listing!(listing_0062_QuadScalar, true);
+test_simulate_listing!(listing_0063_QuadScalarPtr, true);
+test_simulate_listing!(listing_0064_TreeScalarPtr, true);
+test_simulate_listing!(
+    listing_80186_extensions,
+    true,
+    instruction_decoding_8086::cpu::CpuModel::Intel80186
+);
+test_simulate_listing!(listing_segment_overrides, true);
+test_simulate_listing!(listing_stack_call_ret, true);
+test_simulate_listing!(listing_logical_ops, true);
+test_simulate_listing!(listing_shifts_rotates, true);
+test_simulate_listing!(listing_string_ops, true);
+test_simulate_listing!(listing_interrupts, true);
+test_simulate_listing!(listing_mul_div, true);
+test_simulate_listing!(listing_bcd, true);
+test_simulate_listing!(listing_flag_control, true);
+test_simulate_listing!(listing_adc_sbb, true);
+test_simulate_listing!(listing_far_transfers, true);
+test_simulate_listing!(listing_io_bus, true);
+test_simulate_listing!(listing_own_stack_ret, true);
+test_simulate_listing!(listing_word_parity, true);
+test_simulate_listing!(listing_loop_wrap, true);
+
+#[test]
+fn simulate_stops_at_32_bit_operands() {
+    use instruction_decoding_8086::cpu::CpuModel;
+    use instruction_decoding_8086::error::{DecodeError, DecodeErrorReason};
+
+    let store = &mut instruction_decoding_8086::Store::default();
+    store.set_cpu_model(CpuModel::Intel80386);
+
+    // mov ax, 1, then mov eax, 1.
+    let program = vec![0xb8, 0x01, 0x00, 0x66, 0xb8, 0x01, 0x00, 0x00, 0x00];
+    let result = instruction_decoding_8086::simulate(Cursor::new(program), store);
+
+    assert_eq!(
+        result.err(),
+        Some(DecodeError {
+            offset: 3,
+            bytes: vec![0x66, 0xb8, 0x01, 0x00, 0x00, 0x00],
+            reason: DecodeErrorReason::NotSimulated,
+        })
+    );
+}
+
+#[test]
+fn branches_back_past_the_start_wrap_around() {
+    let store = &mut instruction_decoding_8086::Store::default();
+
+    // jmp $-3, to IP 0xfffd past the end of the program rather than onto the nop after it.
+    let program = vec![0xeb, 0xfb, 0x90];
+    let (_, num_cpu_cycles) =
+        instruction_decoding_8086::simulate(Cursor::new(program), store).unwrap();
+
+    assert_eq!(num_cpu_cycles, 15);
+}
+
+#[test]
+fn simulate_records_the_port_accesses_of_listing_io_bus() {
+    use instruction_decoding_8086::io_bus::RecordingIoBus;
+
+    let store = &mut instruction_decoding_8086::Store::default();
+    let program = read_listing("listing_io_bus");
+
+    instruction_decoding_8086::simulate(Cursor::new(program), store).unwrap();
+
+    insta::assert_debug_snapshot!(store.io_bus::<RecordingIoBus>().unwrap().accesses());
+}
@@ -0,0 +1,45 @@
+; Exercises the instructions the 80186 added on top of the 8086.
+
+bits 16
+cpu 186
+
+mov sp, 0x400
+mov ax, 1
+mov bx, 2
+mov cx, 3
+mov dx, 4
+pusha
+mov ax, 0
+mov bx, 0
+popa
+
+push word 1000
+push byte -2
+pop si
+pop di
+imul bp, di, 3
+imul cx, si, -300
+
+shl ax, 4
+sar si, 3
+mov byte [bp + 2], 0x81
+rol byte [bp + 2], 7
+
+mov bp, 0x3F0
+enter 8, 0
+enter 4, 2
+mov word [bp - 2], 7
+leave
+leave
+
+mov word [0x200], 0
+mov word [0x202], 10
+mov bx, 5
+bound bx, [0x200]
+
+mov dx, 0x60
+mov di, 0x300
+mov cx, 2
+rep insw
+mov si, 0x300
+outsb
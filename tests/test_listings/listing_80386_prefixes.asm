@@ -0,0 +1,58 @@
+; Exercises the operand and address size prefixes the 80386 added to real mode code.
+
+bits 16
+cpu 386
+
+mov eax, 0x12345678
+mov ecx, edx
+mov ebx, [si + 4]
+mov [bx], esi
+mov ax, [eax]
+mov eax, [ebx + ecx*4 + 8]
+mov dl, [esp]
+mov ax, [ebp - 2]
+mov cx, [dword 0x1234]
+mov eax, [dword 0x10]
+mov [dword 0x20], al
+mov al, [byte ebx + 0]
+mov al, [dword ebx + 4]
+mov bl, [nosplit esi*1 + 2]
+mov word [ecx + edx], 7
+mov dword [bx + si], 300
+mov es, [ebx]
+mov eax, ds
+
+add eax, ebx
+add eax, 5
+sub ecx, 0x10000
+add dword [di], -1
+cmp esi, [nosplit eax*2 + 16]
+and edi, 0xFF00FF00
+test eax, 1
+test ecx, edx
+
+inc eax
+dec dword [bx]
+neg esi
+not word [edi]
+mul ecx
+shl eax, 4
+sar dword [bp + 2], 1
+
+push eax
+pop ebx
+push dword 100000
+o32 push byte -2
+push dword [si]
+imul eax, ebx, 1000
+imul edx, [ecx], -3
+xchg eax, ecx
+lea esi, [ebx + eax + 12]
+lds si, [eax]
+
+o32 movsw
+a32 rep stosb
+o32 cwd
+jmp dword $+0x100
+call dword $-0x20
+o32 jmp 0x1000:0x12345
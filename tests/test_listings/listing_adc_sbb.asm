@@ -0,0 +1,26 @@
+; Chains additions and subtractions wider than a word through CF with adc and sbb, and takes the
+; incoming carry into every flag when it is what wraps the result.
+
+bits 16
+cpu 8086
+
+mov word [0x300], 0xffff
+mov word [0x302], 0x0001
+mov ax, 0x0001
+mov dx, 0x0002
+add ax, [0x300]
+adc dx, [0x302]
+
+mov bx, 0x0000
+mov cx, 0x0005
+sub bx, 1
+sbb cx, 0
+
+mov si, 0xffff
+stc
+adc si, 0
+mov di, 0
+sbb di, 0
+
+adc byte [0x300], 0x80
+mov bp, [0x300]
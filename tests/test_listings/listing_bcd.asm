@@ -0,0 +1,41 @@
+; Adjusts packed and unpacked BCD after additions, subtractions, a multiplication and before a
+; division, including a daa that carries out of the byte and a das that borrows into it.
+
+bits 16
+cpu 8086
+
+mov al, 0x38
+add al, 0x45
+daa
+mov bl, al
+
+mov al, 0x99
+add al, 0x01
+daa
+mov cl, al
+mov ch, 0
+adc ch, 0
+
+mov al, 0x12
+sub al, 0x25
+das
+mov dl, al
+mov dh, 0
+sbb dh, 0
+
+mov ax, 0x0009
+add al, 8
+aaa
+mov si, ax
+
+mov ax, 0x0203
+sub al, 5
+aas
+mov di, ax
+
+mov al, 63
+aam
+mov bp, ax
+
+mov ax, 0x0407
+aad
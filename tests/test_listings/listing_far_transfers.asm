@@ -0,0 +1,34 @@
+; Transfers control between code segments with far calls and jumps, direct and through far
+; pointers in memory, and returns to the caller's segment with retf, releasing an argument.
+
+bits 16
+cpu 8086
+
+mov sp, 0x400
+mov word [0x300], far_function
+mov word [0x302], 0x1000
+
+mov ax, 7
+push ax
+call far [0x300]
+mov bx, cx
+jmp 0x2000:far_jump
+
+back:
+mov word [0x304], done
+mov word [0x306], 0
+jmp far [0x304]
+mov si, 0xbad
+
+far_function:
+mov bp, sp
+mov cx, [bp + 4]
+retf 2
+
+far_jump:
+mov dx, cs
+mov ax, back
+jmp ax
+
+done:
+mov di, cs
@@ -0,0 +1,25 @@
+; Sets, clears and complements the flags the processor control instructions own, runs the
+; instructions that only wait on a coprocessor that isn't there, and stops at hlt.
+
+bits 16
+cpu 8086
+
+stc
+cmc
+cmc
+mov ax, 0
+adc ax, 0
+
+std
+sti
+pushf
+pop bx
+cld
+cli
+
+wait
+nop
+esc 8, ax
+
+hlt
+mov si, 0xbad
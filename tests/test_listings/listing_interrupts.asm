@@ -0,0 +1,41 @@
+; Raises int n, int3 and into through handlers installed in the interrupt vector table, each
+; returning with iret, then stops at an interrupt whose vector is still null.
+
+bits 16
+cpu 8086
+
+mov sp, 0x400
+mov word [0x84], int_21
+mov word [0x0c], breakpoint
+mov word [0x10], overflow
+
+sti
+mov ax, 1
+int 0x21
+int3
+
+mov al, 0x7f
+add al, 1
+into
+mov al, 1
+add al, 1
+into
+jmp done
+
+int_21:
+add ax, 0x20
+pushf
+pop dx
+iret
+
+breakpoint:
+inc bx
+iret
+
+overflow:
+mov cx, 4
+iret
+
+done:
+int 0x22
+mov si, 0xbad
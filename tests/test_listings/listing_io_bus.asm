@@ -0,0 +1,20 @@
+; Reads and writes bytes and words through fixed ports and through the port in dx. Nothing is
+; attached to the bus, so every read sees it floating high.
+
+bits 16
+cpu 8086
+
+mov al, 0x5a
+out 0x40, al
+mov ax, 0x1234
+mov dx, 0x3f8
+out dx, ax
+
+in al, 0x60
+mov bx, ax
+in ax, dx
+mov cx, ax
+
+mov dx, 0xffff
+mov al, 0
+out dx, al
@@ -0,0 +1,24 @@
+; Exercises and, or, xor, test and not on registers, memory and immediates. All but not clear
+; the carry and overflow and set the sign, zero and parity by the result; not leaves the
+; flags alone, and test only sets them.
+
+bits 16
+cpu 8086
+
+mov ax, 0xf0f0
+mov bx, 0x0ff0
+and ax, bx
+stc
+or bx, 0x8000
+mov word [0x200], 0x5555
+xor [0x200], bx
+mov cx, [0x200]
+mov byte [0x202], 0xff
+and byte [0x202], 0x3c
+mov dh, [0x202]
+xor si, si
+mov al, 0x0f
+test al, 0xf0
+not cx
+mov dl, 0x81
+test dl, 0x80
@@ -0,0 +1,24 @@
+; Counts cx down through 0x8000 with loop, loope and loopne, which wrap it like the unsigned
+; count it is rather than overflowing.
+
+bits 16
+cpu 8086
+
+mov cx, 0x8000
+spin:
+loop spin
+
+mov cx, 0x8000
+loope not_equal
+not_equal:
+mov dx, cx
+
+cmp ax, bx
+mov cx, 0x8000
+loopne not_zero
+not_zero:
+mov si, cx
+
+mov cx, 0
+loop done
+done:
@@ -0,0 +1,44 @@
+; Multiplies and divides bytes and words, signed and unsigned, then divides by zero, which
+; raises the divide error through interrupt 0 before returning after the division.
+
+bits 16
+cpu 8086
+
+mov sp, 0x400
+mov word [0], divide_error
+
+mov al, 200
+mov bl, 3
+mul bl
+mov cx, ax
+
+mov ax, 0x1234
+mov word [0x300], 0x100
+mul word [0x300]
+mov si, dx
+
+mov al, -4
+mov bl, 5
+imul bl
+mov di, ax
+
+mov ax, 1000
+cwd
+mov bx, -7
+idiv bx
+mov bp, dx
+
+mov ax, 100
+mov bl, 7
+div bl
+
+mov dx, 0
+mov bx, 0
+div bx
+jmp done
+
+divide_error:
+mov bx, 0xdead
+iret
+
+done:
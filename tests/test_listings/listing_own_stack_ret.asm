@@ -0,0 +1,18 @@
+; Moves the stack somewhere of its own, calls into it and discards a word it pushed with add, then
+; hands control back to whoever started the program with a ret that pops none of its own words.
+
+bits 16
+cpu 8086
+
+mov sp, 0x400
+call double
+push bx
+add sp, 2
+mov ax, 1
+ret
+mov si, 0xbad
+
+double:
+mov bx, 21
+add bx, bx
+ret
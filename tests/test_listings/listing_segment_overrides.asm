@@ -0,0 +1,27 @@
+; Reads and copies through segment overrides, on memory operands as well as on string
+; operations and xlat, which otherwise read through ds.
+
+bits 16
+
+mov ax, 0x200
+mov es, ax
+mov word [0x1000], 0x1111
+mov word es:[0x1000], 0x2222
+mov word es:[0x1002], 0x3344
+mov bx, [0x1000]
+mov cx, es:[0x1000]
+
+mov si, 0x1000
+es lodsw
+mov dx, ax
+
+mov si, 0x1000
+mov di, 0x1010
+es movsw
+mov cx, 2
+es rep movsb
+mov bp, es:[0x1012]
+
+mov bx, 0x1000
+mov al, 3
+es xlat
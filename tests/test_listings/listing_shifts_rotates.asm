@@ -0,0 +1,30 @@
+; Shifts and rotates by 1 and by cl, on registers and memory. A count of 0 leaves the operand
+; and the flags alone, and the 8086 shifts by the whole of cl, so 17 empties a word.
+
+bits 16
+cpu 8086
+
+mov ax, 0x8001
+shl ax, 1
+mov bx, 0x1234
+mov cl, 0
+shl bx, cl
+mov dx, 0xffff
+mov cl, 17
+shr dx, cl
+mov byte [0x300], 0x81
+shl byte [0x300], 1
+mov si, 0x8000
+mov cl, 3
+sar si, cl
+mov di, 0x8001
+rol di, 1
+mov bp, 0x0001
+ror bp, 1
+stc
+mov al, 0x80
+rcl al, 1
+clc
+mov ah, 0x01
+rcr ah, 1
+mov cl, [0x300]
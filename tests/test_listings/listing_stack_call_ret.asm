@@ -0,0 +1,46 @@
+; Pushes and pops through SS:SP, calls near and far, releases an argument with ret 2, and
+; dispatches through an address pushed for ret, which returns into the program rather than
+; out of it.
+
+bits 16
+cpu 8086
+
+mov sp, 0x400
+mov bx, 0x1111
+push bx
+mov word [0x300], 0x2222
+push word [0x300]
+push cs
+pushf
+stc
+popf
+pop es
+pop cx
+pop dx
+
+mov ax, 5
+push ax
+call add_three
+mov bx, ax
+call 0:far_function
+
+mov ax, dispatched
+push ax
+ret
+mov si, 0xbad
+
+dispatched:
+mov di, 0x600d
+jmp done
+
+add_three:
+mov bp, sp
+mov ax, [bp + 2]
+add ax, 3
+ret 2
+
+far_function:
+mov si, 7
+retf
+
+done:
@@ -0,0 +1,40 @@
+; String operations on their own and under rep, repe and repne. A rep with cx at 0 does
+; nothing, scans and compares stop at the first element that ends the repetition, and the
+; direction flag picks which way si and di step.
+
+bits 16
+cpu 8086
+
+mov word [0x300], 0x1111
+mov word [0x302], 0x2222
+mov word [0x304], 0x3333
+cld
+mov si, 0x300
+mov di, 0x400
+mov cx, 3
+rep movsw
+mov cx, 0
+rep stosb
+
+mov di, 0x400
+mov ax, 0x2222
+mov cx, 3
+repne scasw
+mov bx, cx
+
+mov si, 0x300
+mov di, 0x400
+mov cx, 3
+repe cmpsw
+
+std
+mov si, 0x305
+lodsb
+mov dx, si
+cld
+
+mov di, 0x500
+mov al, 0x7f
+mov cx, 2
+rep stosb
+mov bp, [0x500]
@@ -0,0 +1,21 @@
+; Runs logical operations on words whose high byte has a different parity from their low byte.
+; PF only reflects the low byte, so the high byte never sets or clears it.
+
+bits 16
+cpu 8086
+
+mov sp, 0x400
+
+mov ax, 0x0100
+and ax, 0xffff
+pushf
+pop bx
+
+mov cx, 0x0100
+or cx, 0x0001
+pushf
+pop dx
+
+mov si, 0x1234
+xor si, 0x1334
+test si, 0x0300